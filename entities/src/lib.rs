@@ -2,5 +2,18 @@
 
 pub mod prelude;
 
+pub mod admins;
+pub mod blocks;
+pub mod broadcast_deliveries;
+pub mod broadcasts;
+pub mod conversations;
+pub mod deleted_link_codes;
+pub mod dialogues;
+pub mod dropped_messages;
+pub mod filters;
+pub mod held_messages;
+pub mod links;
 pub mod messages;
+pub mod reports;
+pub mod throttles;
 pub mod users;
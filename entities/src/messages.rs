@@ -12,10 +12,41 @@ pub struct Model {
     pub recipient_id: i64,
     pub recipient_message_id: i32,
     pub timestamp: DateTime,
+    pub link_id: Option<i32>,
+    pub retracted_at: Option<DateTime>,
+    /// Parts of one album share it.
+    pub media_group_id: Option<String>,
+    pub conversation_id: i32,
+    /// The message this one replies to.
+    pub reply_to: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::conversations::Entity",
+        from = "Column::ConversationId",
+        to = "super::conversations::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Conversations,
+    #[sea_orm(
+        belongs_to = "super::links::Entity",
+        from = "Column::LinkId",
+        to = "super::links::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Links,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ReplyTo",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SelfRef,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::RecipientId",
@@ -34,4 +65,16 @@ pub enum Relation {
     Users1,
 }
 
+impl Related<super::conversations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Conversations.def()
+    }
+}
+
+impl Related<super::links::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Links.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,17 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.10
 
+pub use super::admins::Entity as Admins;
+pub use super::blocks::Entity as Blocks;
+pub use super::broadcast_deliveries::Entity as BroadcastDeliveries;
+pub use super::broadcasts::Entity as Broadcasts;
+pub use super::conversations::Entity as Conversations;
+pub use super::deleted_link_codes::Entity as DeletedLinkCodes;
+pub use super::dialogues::Entity as Dialogues;
+pub use super::dropped_messages::Entity as DroppedMessages;
+pub use super::filters::Entity as Filters;
+pub use super::held_messages::Entity as HeldMessages;
+pub use super::links::Entity as Links;
 pub use super::messages::Entity as Messages;
+pub use super::reports::Entity as Reports;
+pub use super::throttles::Entity as Throttles;
 pub use super::users::Entity as Users;
@@ -9,10 +9,25 @@ pub struct Model {
     pub id: i64,
     pub invited_by: Option<i64>,
     pub last_activity: DateTime,
-    #[sea_orm(unique)]
-    pub link: String,
     pub first_activity: DateTime,
     pub answer_tip: bool,
+    pub language: Option<String>,
+    pub language_code: Option<String>,
+    pub banned_at: Option<DateTime>,
+    pub ban_expires_at: Option<DateTime>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub ban_reason: Option<String>,
+    pub banned_by: Option<i64>,
+    pub ban_notified_at: Option<DateTime>,
+    pub paused: bool,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub closed_note: Option<String>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub schedule: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub accepted_types: Option<Json>,
+    pub max_text_length: Option<i32>,
+    pub retention_days: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
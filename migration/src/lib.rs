@@ -4,6 +4,28 @@ mod m20220101_000001_create_table;
 mod m20240129_132329_create_messages;
 mod m20240129_173538_add_timestamps;
 mod m20240720_120000_add_answer_tip_field;
+mod m20241221_120000_create_dialogues;
+mod m20241222_120000_add_language_fields;
+mod m20241223_120000_create_admins;
+mod m20241224_120000_create_broadcasts;
+mod m20241225_120000_add_broadcast_content;
+mod m20241226_120000_create_blocks;
+mod m20241227_120000_create_reports;
+mod m20241228_120000_add_user_bans;
+mod m20241229_120000_create_links;
+mod m20241230_120000_add_link_labels;
+mod m20241231_120000_add_inbox_schedule;
+mod m20250101_120000_create_throttles;
+mod m20250102_120000_add_accepted_types;
+mod m20250103_120000_create_filters;
+mod m20250104_120000_add_message_retraction;
+mod m20250105_120000_add_message_media_groups;
+mod m20250106_120000_create_conversations;
+mod m20250107_120000_add_message_replies;
+mod m20250108_120000_create_deleted_link_codes;
+mod m20250109_120000_keep_reports_of_deleted_users;
+mod m20250110_120000_add_retention;
+mod m20250111_120000_create_dropped_messages;
 
 pub struct Migrator;
 
@@ -15,6 +37,28 @@ impl MigratorTrait for Migrator {
             Box::new(m20240129_132329_create_messages::Migration),
             Box::new(m20240129_173538_add_timestamps::Migration),
             Box::new(m20240720_120000_add_answer_tip_field::Migration),
+            Box::new(m20241221_120000_create_dialogues::Migration),
+            Box::new(m20241222_120000_add_language_fields::Migration),
+            Box::new(m20241223_120000_create_admins::Migration),
+            Box::new(m20241224_120000_create_broadcasts::Migration),
+            Box::new(m20241225_120000_add_broadcast_content::Migration),
+            Box::new(m20241226_120000_create_blocks::Migration),
+            Box::new(m20241227_120000_create_reports::Migration),
+            Box::new(m20241228_120000_add_user_bans::Migration),
+            Box::new(m20241229_120000_create_links::Migration),
+            Box::new(m20241230_120000_add_link_labels::Migration),
+            Box::new(m20241231_120000_add_inbox_schedule::Migration),
+            Box::new(m20250101_120000_create_throttles::Migration),
+            Box::new(m20250102_120000_add_accepted_types::Migration),
+            Box::new(m20250103_120000_create_filters::Migration),
+            Box::new(m20250104_120000_add_message_retraction::Migration),
+            Box::new(m20250105_120000_add_message_media_groups::Migration),
+            Box::new(m20250106_120000_create_conversations::Migration),
+            Box::new(m20250107_120000_add_message_replies::Migration),
+            Box::new(m20250108_120000_create_deleted_link_codes::Migration),
+            Box::new(m20250109_120000_keep_reports_of_deleted_users::Migration),
+            Box::new(m20250110_120000_add_retention::Migration),
+            Box::new(m20250111_120000_create_dropped_messages::Migration),
         ]
     }
 }
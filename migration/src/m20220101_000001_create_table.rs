@@ -45,4 +45,17 @@ pub enum Users {
     LastActivity,
     FirstActivity,
     AnswerTip,
+    Language,
+    LanguageCode,
+    BannedAt,
+    BanExpiresAt,
+    BanReason,
+    BannedBy,
+    BanNotifiedAt,
+    Paused,
+    ClosedNote,
+    Schedule,
+    AcceptedTypes,
+    MaxTextLength,
+    RetentionDays,
 }
@@ -66,4 +66,9 @@ pub enum Messages {
     RecipientId,
     RecipientMessageId,
     Timestamp,
+    LinkId,
+    RetractedAt,
+    MediaGroupId,
+    ConversationId,
+    ReplyTo,
 }
@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Broadcasts::Table)
+                    .col(
+                        ColumnDef::new(Broadcasts::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Broadcasts::CreatedBy)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Broadcasts::Text).text().not_null())
+                    .col(ColumnDef::new(Broadcasts::Status).string().not_null())
+                    .col(
+                        ColumnDef::new(Broadcasts::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BroadcastDeliveries::Table)
+                    .col(
+                        ColumnDef::new(BroadcastDeliveries::BroadcastId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BroadcastDeliveries::Table, BroadcastDeliveries::BroadcastId)
+                            .to(Broadcasts::Table, Broadcasts::Id),
+                    )
+                    .col(
+                        ColumnDef::new(BroadcastDeliveries::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BroadcastDeliveries::Table, BroadcastDeliveries::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(BroadcastDeliveries::BroadcastId)
+                            .col(BroadcastDeliveries::UserId),
+                    )
+                    .col(
+                        ColumnDef::new(BroadcastDeliveries::Status)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BroadcastDeliveries::Error).text())
+                    .col(
+                        ColumnDef::new(BroadcastDeliveries::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BroadcastDeliveries::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Broadcasts::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Broadcasts {
+    Table,
+    Id,
+    CreatedBy,
+    Text,
+    Status,
+    Timestamp,
+    FromChatId,
+    MessageId,
+    Segment,
+}
+
+#[derive(DeriveIden)]
+pub enum BroadcastDeliveries {
+    Table,
+    BroadcastId,
+    UserId,
+    Status,
+    Error,
+    Timestamp,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20241224_120000_create_broadcasts::Broadcasts;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Broadcasts::Table)
+                    .modify_column(ColumnDef::new(Broadcasts::Text).text().null())
+                    .add_column(ColumnDef::new(Broadcasts::FromChatId).big_integer())
+                    .add_column(ColumnDef::new(Broadcasts::MessageId).integer())
+                    .add_column(
+                        ColumnDef::new(Broadcasts::Segment)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'{}'::jsonb")),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Broadcasts::Table)
+                    .modify_column(ColumnDef::new(Broadcasts::Text).text().not_null())
+                    .drop_column(Broadcasts::FromChatId)
+                    .drop_column(Broadcasts::MessageId)
+                    .drop_column(Broadcasts::Segment)
+                    .to_owned(),
+            )
+            .await
+    }
+}
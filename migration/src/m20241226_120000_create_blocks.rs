@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Blocks::Table)
+                    .col(
+                        ColumnDef::new(Blocks::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Blocks::RecipientId).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Blocks::Table, Blocks::RecipientId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(ColumnDef::new(Blocks::SenderId).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Blocks::Table, Blocks::SenderId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(
+                        ColumnDef::new(Blocks::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .index(
+                        Index::create()
+                            .unique()
+                            .col(Blocks::RecipientId)
+                            .col(Blocks::SenderId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Blocks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Blocks {
+    Table,
+    Id,
+    RecipientId,
+    SenderId,
+    Timestamp,
+}
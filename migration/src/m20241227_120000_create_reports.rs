@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Reports::Table)
+                    .col(
+                        ColumnDef::new(Reports::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Reports::ReporterId).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Reports::Table, Reports::ReporterId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(
+                        ColumnDef::new(Reports::ReporterMessageId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Reports::SenderId).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Reports::Table, Reports::SenderId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(
+                        ColumnDef::new(Reports::SenderMessageId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Reports::Reason).string().not_null())
+                    .col(ColumnDef::new(Reports::Status).string().not_null())
+                    .col(ColumnDef::new(Reports::ModeratorId).big_integer())
+                    .col(
+                        ColumnDef::new(Reports::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Reports::DecidedAt).date_time())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Reports::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Reports {
+    Table,
+    Id,
+    ReporterId,
+    ReporterMessageId,
+    SenderId,
+    SenderMessageId,
+    Reason,
+    Status,
+    ModeratorId,
+    Timestamp,
+    DecidedAt,
+}
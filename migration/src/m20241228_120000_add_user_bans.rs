@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::BannedAt).date_time())
+                    .add_column(ColumnDef::new(Users::BanExpiresAt).date_time())
+                    .add_column(ColumnDef::new(Users::BanReason).text())
+                    .add_column(ColumnDef::new(Users::BannedBy).big_integer())
+                    .add_column(ColumnDef::new(Users::BanNotifiedAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        // bans used to be derived from reports decided as "banned"
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE users SET banned_at = reports.decided_at, \
+                    ban_reason = 'report #' || reports.id, \
+                    banned_by = reports.moderator_id \
+                FROM reports \
+                WHERE reports.sender_id = users.id AND reports.status = 'banned'",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::BannedAt)
+                    .drop_column(Users::BanExpiresAt)
+                    .drop_column(Users::BanReason)
+                    .drop_column(Users::BannedBy)
+                    .drop_column(Users::BanNotifiedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
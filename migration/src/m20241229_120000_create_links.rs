@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Links::Table)
+                    .col(
+                        ColumnDef::new(Links::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Links::OwnerId).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Links::Table, Links::OwnerId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(ColumnDef::new(Links::Code).string().not_null().unique_key())
+                    .col(
+                        ColumnDef::new(Links::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Links::DisabledAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO links (owner_id, code, timestamp) \
+                SELECT id, link, first_activity FROM users",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::Link)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::Link).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // the oldest enabled link is the one users had before /newlink
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE users SET link = main_links.code FROM \
+                (SELECT DISTINCT ON (owner_id) owner_id, code FROM links \
+                WHERE disabled_at IS NULL ORDER BY owner_id, id) main_links \
+                WHERE main_links.owner_id = users.id",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .modify_column(ColumnDef::new(Users::Link).string().not_null().unique_key())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Links::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Links {
+    Table,
+    Id,
+    OwnerId,
+    Code,
+    Timestamp,
+    DisabledAt,
+    Label,
+    Opens,
+}
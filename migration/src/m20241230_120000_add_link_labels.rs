@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20240129_132329_create_messages::Messages, m20241229_120000_create_links::Links};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Links::Table)
+                    .add_column(ColumnDef::new(Links::Label).string())
+                    .add_column(ColumnDef::new(Links::Opens).integer().not_null().default(0))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .add_column(ColumnDef::new(Messages::LinkId).integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .from_tbl(Messages::Table)
+                            .from_col(Messages::LinkId)
+                            .to_tbl(Links::Table)
+                            .to_col(Links::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .drop_column(Messages::LinkId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // only one link per user survives the rollback
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM links WHERE label IS NOT NULL")
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Links::Table)
+                    .drop_column(Links::Label)
+                    .drop_column(Links::Opens)
+                    .to_owned(),
+            )
+            .await
+    }
+}
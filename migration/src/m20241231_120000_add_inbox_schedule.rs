@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        ColumnDef::new(Users::Paused)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(ColumnDef::new(Users::ClosedNote).text())
+                    .add_column(ColumnDef::new(Users::Schedule).json_binary())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::Paused)
+                    .drop_column(Users::ClosedNote)
+                    .drop_column(Users::Schedule)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_table::Users, m20240129_132329_create_messages::Messages};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Throttles::Table)
+                    .col(
+                        ColumnDef::new(Throttles::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Throttles::SenderId).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Throttles::Table, Throttles::SenderId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(
+                        ColumnDef::new(Throttles::RecipientId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Throttles::Table, Throttles::RecipientId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(ColumnDef::new(Throttles::Scope).string().not_null())
+                    .col(
+                        ColumnDef::new(Throttles::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // rate limits count recent messages by sender and by recipient
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-messages-sender-timestamp")
+                    .table(Messages::Table)
+                    .col(Messages::SenderId)
+                    .col(Messages::Timestamp)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-messages-recipient-timestamp")
+                    .table(Messages::Table)
+                    .col(Messages::RecipientId)
+                    .col(Messages::Timestamp)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-messages-recipient-timestamp")
+                    .table(Messages::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-messages-sender-timestamp")
+                    .table(Messages::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Throttles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Throttles {
+    Table,
+    Id,
+    SenderId,
+    RecipientId,
+    Scope,
+    Timestamp,
+}
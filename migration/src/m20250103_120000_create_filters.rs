@@ -0,0 +1,131 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_table::Users, m20241229_120000_create_links::Links};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Filters::Table)
+                    .col(
+                        ColumnDef::new(Filters::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Filters::OwnerId).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Filters::Table, Filters::OwnerId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(ColumnDef::new(Filters::Kind).string().not_null())
+                    .col(ColumnDef::new(Filters::Pattern).text())
+                    .col(ColumnDef::new(Filters::Action).string().not_null())
+                    .col(
+                        ColumnDef::new(Filters::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(HeldMessages::Table)
+                    .col(
+                        ColumnDef::new(HeldMessages::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(HeldMessages::SenderId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(HeldMessages::Table, HeldMessages::SenderId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(
+                        ColumnDef::new(HeldMessages::SenderMessageId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(HeldMessages::RecipientId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(HeldMessages::Table, HeldMessages::RecipientId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(ColumnDef::new(HeldMessages::ReplyTo).integer())
+                    .col(ColumnDef::new(HeldMessages::LinkId).integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(HeldMessages::Table, HeldMessages::LinkId)
+                            .to(Links::Table, Links::Id),
+                    )
+                    .col(ColumnDef::new(HeldMessages::Reason).text().not_null())
+                    .col(
+                        ColumnDef::new(HeldMessages::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(HeldMessages::PreviewMessageId).integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(HeldMessages::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Filters::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Filters {
+    Table,
+    Id,
+    OwnerId,
+    Kind,
+    Pattern,
+    Action,
+    Timestamp,
+}
+
+#[derive(DeriveIden)]
+pub enum HeldMessages {
+    Table,
+    Id,
+    SenderId,
+    SenderMessageId,
+    RecipientId,
+    ReplyTo,
+    LinkId,
+    Reason,
+    Timestamp,
+    PreviewMessageId,
+    MediaGroupId,
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20240129_132329_create_messages::Messages, m20250103_120000_create_filters::HeldMessages,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .add_column(ColumnDef::new(Messages::MediaGroupId).string())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeldMessages::Table)
+                    .add_column(ColumnDef::new(HeldMessages::MediaGroupId).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeldMessages::Table)
+                    .drop_column(HeldMessages::MediaGroupId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .drop_column(Messages::MediaGroupId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,159 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20220101_000001_create_table::Users, m20240129_132329_create_messages::Messages,
+    m20241229_120000_create_links::Links,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Conversations::Table)
+                    .col(
+                        ColumnDef::new(Conversations::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Conversations::SenderId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Conversations::Table, Conversations::SenderId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(
+                        ColumnDef::new(Conversations::RecipientId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Conversations::Table, Conversations::RecipientId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(ColumnDef::new(Conversations::LinkId).integer())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Conversations::Table, Conversations::LinkId)
+                            .to(Links::Table, Links::Id),
+                    )
+                    .col(
+                        ColumnDef::new(Conversations::Status)
+                            .string()
+                            .not_null()
+                            .default("open"),
+                    )
+                    .col(
+                        ColumnDef::new(Conversations::Timestamp)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Conversations::LastActivity)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .index(
+                        Index::create()
+                            .col(Conversations::SenderId)
+                            .col(Conversations::RecipientId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .add_column(ColumnDef::new(Messages::ConversationId).integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .from_tbl(Messages::Table)
+                            .from_col(Messages::ConversationId)
+                            .to_tbl(Conversations::Table)
+                            .to_col(Conversations::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // one conversation per pair of users, started by whoever sent the first message,
+        // since a reply always follows a message sent via a link
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "INSERT INTO conversations (sender_id, recipient_id, link_id, timestamp, last_activity) \
+            SELECT DISTINCT ON (LEAST(sender_id, recipient_id), GREATEST(sender_id, recipient_id)) \
+            sender_id, recipient_id, link_id, timestamp, timestamp FROM messages \
+            ORDER BY LEAST(sender_id, recipient_id), GREATEST(sender_id, recipient_id), id",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE messages SET conversation_id = conversations.id FROM conversations \
+            WHERE (messages.sender_id = conversations.sender_id \
+            AND messages.recipient_id = conversations.recipient_id) \
+            OR (messages.sender_id = conversations.recipient_id \
+            AND messages.recipient_id = conversations.sender_id)",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE conversations SET last_activity = m.last_activity FROM \
+            (SELECT conversation_id, MAX(timestamp) AS last_activity FROM messages \
+            GROUP BY conversation_id) m \
+            WHERE m.conversation_id = conversations.id",
+        )
+        .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .modify_column(
+                        ColumnDef::new(Messages::ConversationId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .drop_column(Messages::ConversationId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Conversations::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Conversations {
+    Table,
+    Id,
+    SenderId,
+    RecipientId,
+    LinkId,
+    Status,
+    Timestamp,
+    LastActivity,
+}
@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240129_132329_create_messages::Messages;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .add_column(ColumnDef::new(Messages::ReplyTo).integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .from_tbl(Messages::Table)
+                            .from_col(Messages::ReplyTo)
+                            .to_tbl(Messages::Table)
+                            .to_col(Messages::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .drop_column(Messages::ReplyTo)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20220101_000001_create_table::Users, m20241227_120000_create_reports::Reports};
+
+/// Postgres' name for the constraint created along with the table.
+const SENDER_FOREIGN_KEY: &str = "reports_sender_id_fkey";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // reports outlive the reported sender's account
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name(SENDER_FOREIGN_KEY)
+                    .table(Reports::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM reports WHERE NOT EXISTS \
+                (SELECT 1 FROM users WHERE users.id = reports.sender_id)",
+            )
+            .await?;
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name(SENDER_FOREIGN_KEY)
+                    .from(Reports::Table, Reports::SenderId)
+                    .to(Users::Table, Users::Id)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+};
+use tracing::*;
+
+use crate::{db::Db, i18n::Lang, Bot};
+
+/// Asks to confirm the deletion, the buttons send `delete_me:confirm` and `delete_me:cancel`.
+pub async fn handle_command_delete_me(
+    bot: Bot,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+) -> Result<()> {
+    if db.get_user(msg.chat.id.0).await?.is_none() {
+        bot.send_message(msg.chat.id, lang.nothing_to_delete())
+            .await?;
+        return Ok(());
+    }
+    bot.send_message(msg.chat.id, lang.delete_me_confirmation())
+        .reply_markup(InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::callback(lang.delete_me_button(), "delete_me:confirm"),
+            InlineKeyboardButton::callback(lang.cancel_button(), "delete_me:cancel"),
+        ]]))
+        .await?;
+    Ok(())
+}
+
+pub async fn handle_delete_button(
+    bot: &Bot,
+    db: &Db,
+    q: &CallbackQuery,
+    chat_id: ChatId,
+    confirmed: bool,
+    lang: Lang,
+) -> Result<()> {
+    let prompt = q.message.as_ref().context("no message")?.id();
+    if !confirmed {
+        bot.delete_message(chat_id, prompt).await?;
+        return Ok(());
+    }
+
+    db.delete_user(chat_id.0).await?;
+    info!("user {chat_id} deleted their account");
+    bot.edit_message_text(chat_id, prompt, lang.account_deleted())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,597 @@
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use dptree::case;
+use teloxide::{
+    dispatching::UpdateHandler,
+    macros::BotCommands,
+    prelude::*,
+    types::{
+        BotCommandScope, InlineKeyboardButton, InlineKeyboardMarkup, MessageId, Recipient,
+        ReplyParameters,
+    },
+    utils::command::BotCommands as _,
+};
+use tracing::*;
+
+use crate::{
+    broadcast::{BroadcastStatus, Segment},
+    db::Db,
+    links::{self, CodeError},
+    Bot, Command, UserLink,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AdminRole {
+    Owner,
+    Moderator,
+}
+
+impl AdminRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdminRole::Owner => "owner",
+            AdminRole::Moderator => "moderator",
+        }
+    }
+}
+
+impl FromStr for AdminRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "owner" => Ok(AdminRole::Owner),
+            "moderator" => Ok(AdminRole::Moderator),
+            _ => bail!("unknown admin role: {s}"),
+        }
+    }
+}
+
+#[derive(BotCommands, PartialEq, Debug, Clone)]
+#[command(rename_rule = "lowercase")]
+pub enum AdminCommand {
+    #[command(description = "List admins")]
+    Admins,
+    #[command(description = "Add an admin: /addadmin <user id> [owner|moderator]")]
+    AddAdmin(String),
+    #[command(description = "Remove an admin: /removeadmin <user id>")]
+    RemoveAdmin(String),
+    #[command(
+        description = "Broadcast the replied message: /broadcast [filters], or /broadcast <text> to everyone"
+    )]
+    Broadcast(String),
+    #[command(
+        description = "Count users matching filters: active=<days> inactive=<days> joined=<days> ref=<user id|link> lang=<code>"
+    )]
+    Audience(String),
+    #[command(description = "List recent broadcasts")]
+    Broadcasts,
+    #[command(description = "Pause a broadcast: /pausebroadcast <id>")]
+    PauseBroadcast(String),
+    #[command(description = "Resume a broadcast: /resumebroadcast <id>")]
+    ResumeBroadcast(String),
+    #[command(description = "Cancel a broadcast: /cancelbroadcast <id>")]
+    CancelBroadcast(String),
+    #[command(description = "Ban a user: /ban <user id|link> [30m|12h|7d] [reason]")]
+    Ban(String),
+    #[command(description = "Unban a user: /unban <user id|link>")]
+    Unban(String),
+    #[command(
+        description = "Take a link code from its owner: /reclaimlink <code> [user id|link to give it to]"
+    )]
+    ReclaimLink(String),
+    #[command(description = "Senders hitting rate limits: /throttled [hours]")]
+    Throttled(String),
+}
+
+pub fn admin_command_handler() -> UpdateHandler<anyhow::Error> {
+    teloxide::filter_command::<AdminCommand, _>()
+        .filter_map_async(|db: Arc<Db>, msg: Message| async move {
+            db.admin_role(msg.chat.id.0).await.ok().flatten()
+        })
+        .branch(case![AdminCommand::Admins].endpoint(handle_admins))
+        .branch(case![AdminCommand::Ban(args)].endpoint(handle_ban))
+        .branch(case![AdminCommand::Unban(args)].endpoint(handle_unban))
+        .branch(case![AdminCommand::ReclaimLink(args)].endpoint(handle_reclaim_link))
+        .branch(case![AdminCommand::Throttled(hours)].endpoint(handle_throttled))
+        .branch(
+            dptree::filter(|role: AdminRole| role == AdminRole::Owner)
+                .branch(case![AdminCommand::AddAdmin(args)].endpoint(handle_add_admin))
+                .branch(case![AdminCommand::RemoveAdmin(args)].endpoint(handle_remove_admin))
+                .branch(case![AdminCommand::Broadcast(args)].endpoint(handle_broadcast))
+                .branch(case![AdminCommand::Audience(args)].endpoint(handle_audience))
+                .branch(case![AdminCommand::Broadcasts].endpoint(handle_broadcasts))
+                .branch(case![AdminCommand::PauseBroadcast(id)].endpoint(handle_pause_broadcast))
+                .branch(case![AdminCommand::ResumeBroadcast(id)].endpoint(handle_resume_broadcast))
+                .branch(case![AdminCommand::CancelBroadcast(id)].endpoint(handle_cancel_broadcast)),
+        )
+        .branch(dptree::endpoint(handle_owner_only))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BroadcastAction {
+    Send(i32),
+    Discard(i32),
+}
+
+impl FromStr for BroadcastAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            Some(("broadcast_send", id)) => Ok(BroadcastAction::Send(id.parse()?)),
+            Some(("broadcast_discard", id)) => Ok(BroadcastAction::Discard(id.parse()?)),
+            _ => bail!("unknown broadcast action: {s}"),
+        }
+    }
+}
+
+pub fn admin_callback_handler() -> UpdateHandler<anyhow::Error> {
+    dptree::filter_map(|q: CallbackQuery| q.data?.parse::<BroadcastAction>().ok())
+        .filter_map_async(|db: Arc<Db>, q: CallbackQuery| async move {
+            db.admin_role(ChatId::from(q.from.id).0)
+                .await
+                .ok()
+                .flatten()
+        })
+        .filter(|role: AdminRole| role == AdminRole::Owner)
+        .endpoint(handle_broadcast_action)
+}
+
+/// Registers owners from `ADMIN_IDS` and shows admin commands to everyone in the admins table.
+pub async fn init_admins(bot: &Bot, db: &Db) -> Result<()> {
+    if let Ok(ids) = std::env::var("ADMIN_IDS") {
+        for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            let id = id.parse().context("invalid ADMIN_IDS")?;
+            db.add_admin(id, AdminRole::Owner, None).await?;
+        }
+    }
+
+    for (id, _) in db.get_admins().await? {
+        if let Err(e) = set_admin_commands(bot, db, id).await {
+            warn!("can't set admin commands for {id}: {e}");
+        }
+    }
+    Ok(())
+}
+
+pub async fn set_admin_commands(bot: &Bot, db: &Db, user_id: i64) -> Result<()> {
+    let lang = db.user_lang(user_id, None).await?;
+    let mut commands = lang.bot_commands(Command::bot_commands());
+    commands.extend(AdminCommand::bot_commands());
+    bot.set_my_commands(commands)
+        .scope(BotCommandScope::Chat {
+            chat_id: Recipient::Id(ChatId(user_id)),
+        })
+        .await?;
+    Ok(())
+}
+
+async fn handle_admins(bot: Bot, db: Arc<Db>, msg: Message) -> Result<()> {
+    let admins = db
+        .get_admins()
+        .await?
+        .into_iter()
+        .map(|(id, role)| format!("{id} - {}", role.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    bot.send_message(msg.chat.id, admins).await?;
+    Ok(())
+}
+
+async fn handle_add_admin(bot: Bot, db: Arc<Db>, msg: Message, args: String) -> Result<()> {
+    let mut args = args.split_whitespace();
+    let (Some(Ok(id)), role) = (args.next().map(str::parse::<i64>), args.next()) else {
+        bot.send_message(msg.chat.id, "Usage: /addadmin <user id> [owner|moderator]")
+            .await?;
+        return Ok(());
+    };
+    let role = match role.map(AdminRole::from_str).transpose() {
+        Ok(role) => role.unwrap_or(AdminRole::Moderator),
+        Err(e) => {
+            bot.send_message(msg.chat.id, e.to_string()).await?;
+            return Ok(());
+        }
+    };
+
+    db.add_admin(id, role, Some(msg.chat.id.0)).await?;
+    if let Err(e) = set_admin_commands(&bot, &db, id).await {
+        warn!("can't set admin commands for {id}: {e}");
+    }
+    bot.send_message(msg.chat.id, format!("{id} is now {}", role.as_str()))
+        .await?;
+    Ok(())
+}
+
+async fn handle_remove_admin(bot: Bot, db: Arc<Db>, msg: Message, args: String) -> Result<()> {
+    let Ok(id) = args.trim().parse::<i64>() else {
+        bot.send_message(msg.chat.id, "Usage: /removeadmin <user id>")
+            .await?;
+        return Ok(());
+    };
+    if id == msg.chat.id.0 {
+        bot.send_message(msg.chat.id, "You can't remove yourself")
+            .await?;
+        return Ok(());
+    }
+
+    if db.remove_admin(id).await? {
+        if let Err(e) = bot
+            .delete_my_commands()
+            .scope(BotCommandScope::Chat {
+                chat_id: Recipient::Id(ChatId(id)),
+            })
+            .await
+        {
+            warn!("can't delete admin commands for {id}: {e}");
+        }
+        bot.send_message(msg.chat.id, format!("{id} is no longer an admin"))
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, format!("{id} is not an admin"))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Accepts either a user id or a link code.
+async fn resolve_user(db: &Db, user: &str) -> Result<Option<i64>> {
+    match user.parse() {
+        Ok(id) => Ok(Some(id)),
+        Err(_) => db.user_id_by_link(user).await,
+    }
+}
+
+/// Parses durations like `30m`, `12h` or `7d` into seconds.
+fn parse_duration(s: &str) -> Option<u64> {
+    let unit = match s.chars().last()? {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+    let amount: u64 = s[..s.len() - 1].parse().ok()?;
+    amount.checked_mul(unit)
+}
+
+async fn parse_segment(db: &Db, filters: &str) -> Result<Segment> {
+    let mut segment = Segment::default();
+    for filter in filters.split_whitespace() {
+        let Some((key, value)) = filter.split_once('=') else {
+            bail!("filters look like key=value, got {filter}");
+        };
+        match key {
+            "active" => segment.active_days = Some(value.parse()?),
+            "inactive" => segment.inactive_days = Some(value.parse()?),
+            "joined" => segment.joined_days = Some(value.parse()?),
+            "ref" => {
+                let id = resolve_user(db, value)
+                    .await?
+                    .context(format!("no user with link {value}"))?;
+                segment.invited_by = Some(id);
+            }
+            "lang" => segment.language = Some(value.to_lowercase()),
+            _ => bail!("unknown filter: {key}"),
+        }
+    }
+    Ok(segment)
+}
+
+async fn handle_broadcast(bot: Bot, db: Arc<Db>, msg: Message, args: String) -> Result<()> {
+    let (text, message, filters) = match msg.reply_to_message() {
+        Some(reply) => (None, Some((reply.chat.id.0, reply.id.0)), args.as_str()),
+        None if !args.trim().is_empty() => (Some(args.clone()), None, ""),
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                "Reply to the message to broadcast with /broadcast [filters], or use /broadcast <text>",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let segment = match parse_segment(&db, filters).await {
+        Ok(segment) => segment,
+        Err(e) => {
+            bot.send_message(msg.chat.id, e.to_string()).await?;
+            return Ok(());
+        }
+    };
+
+    let id = db
+        .create_broadcast(msg.chat.id.0, text.clone(), message, &segment)
+        .await?;
+    let audience = db.count_segment(&segment).await?;
+
+    let preview = match (text, message) {
+        (_, Some((chat_id, message_id))) => {
+            bot.copy_message(msg.chat.id, ChatId(chat_id), MessageId(message_id))
+                .await?
+        }
+        (text, None) => {
+            bot.send_message(msg.chat.id, text.unwrap_or_default())
+                .await?
+                .id
+        }
+    };
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "Broadcast #{id} preview above. Audience: {}, {audience} users. Send it?",
+            segment.describe()
+        ),
+    )
+    .reply_parameters(ReplyParameters::new(preview))
+    .reply_markup(InlineKeyboardMarkup::new([[
+        InlineKeyboardButton::callback("Send", format!("broadcast_send:{id}")),
+        InlineKeyboardButton::callback("Discard", format!("broadcast_discard:{id}")),
+    ]]))
+    .await?;
+    Ok(())
+}
+
+async fn handle_audience(bot: Bot, db: Arc<Db>, msg: Message, args: String) -> Result<()> {
+    let text = match parse_segment(&db, &args).await {
+        Ok(segment) => format!(
+            "{}: {} users",
+            segment.describe(),
+            db.count_segment(&segment).await?
+        ),
+        Err(e) => e.to_string(),
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_broadcast_action(
+    bot: Bot,
+    db: Arc<Db>,
+    q: CallbackQuery,
+    action: BroadcastAction,
+) -> Result<()> {
+    let text = match action {
+        BroadcastAction::Send(id) => {
+            if db
+                .set_broadcast_status(id, &[BroadcastStatus::Draft], BroadcastStatus::Running)
+                .await?
+            {
+                format!("Broadcast #{id} started, progress will be reported here")
+            } else {
+                format!("Broadcast #{id} is not a draft anymore")
+            }
+        }
+        BroadcastAction::Discard(id) => {
+            db.set_broadcast_status(id, &[BroadcastStatus::Draft], BroadcastStatus::Cancelled)
+                .await?;
+            format!("Broadcast #{id} discarded")
+        }
+    };
+    if let Some(message) = &q.message {
+        bot.edit_message_text(message.chat().id, message.id(), text)
+            .await?;
+    }
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
+async fn handle_broadcasts(bot: Bot, db: Arc<Db>, msg: Message) -> Result<()> {
+    let mut lines = Vec::new();
+    for broadcast in db.get_last_broadcasts(10).await? {
+        let stats = db.broadcast_stats(broadcast.id).await?;
+        lines.push(stats.describe(&broadcast));
+    }
+    if lines.is_empty() {
+        lines.push("No broadcasts yet".to_owned());
+    }
+    bot.send_message(msg.chat.id, lines.join("\n")).await?;
+    Ok(())
+}
+
+async fn handle_pause_broadcast(bot: Bot, db: Arc<Db>, msg: Message, id: String) -> Result<()> {
+    change_broadcast_status(
+        &bot,
+        &db,
+        &msg,
+        &id,
+        &[BroadcastStatus::Running],
+        BroadcastStatus::Paused,
+    )
+    .await
+}
+
+async fn handle_resume_broadcast(bot: Bot, db: Arc<Db>, msg: Message, id: String) -> Result<()> {
+    change_broadcast_status(
+        &bot,
+        &db,
+        &msg,
+        &id,
+        &[BroadcastStatus::Paused],
+        BroadcastStatus::Running,
+    )
+    .await
+}
+
+async fn handle_cancel_broadcast(bot: Bot, db: Arc<Db>, msg: Message, id: String) -> Result<()> {
+    change_broadcast_status(
+        &bot,
+        &db,
+        &msg,
+        &id,
+        &[BroadcastStatus::Running, BroadcastStatus::Paused],
+        BroadcastStatus::Cancelled,
+    )
+    .await
+}
+
+async fn change_broadcast_status(
+    bot: &Bot,
+    db: &Db,
+    msg: &Message,
+    id: &str,
+    from: &[BroadcastStatus],
+    to: BroadcastStatus,
+) -> Result<()> {
+    let Ok(id) = id.trim().parse::<i32>() else {
+        bot.send_message(msg.chat.id, "Usage: /<command> <broadcast id>")
+            .await?;
+        return Ok(());
+    };
+
+    if db.set_broadcast_status(id, from, to).await? {
+        bot.send_message(msg.chat.id, format!("Broadcast #{id} is {}", to.as_str()))
+            .await?;
+    } else {
+        bot.send_message(
+            msg.chat.id,
+            format!("Broadcast #{id} not found or can't be {}", to.as_str()),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+async fn handle_ban(bot: Bot, db: Arc<Db>, msg: Message, args: String) -> Result<()> {
+    let args = args.trim();
+    let (user, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let Some(id) = resolve_user(&db, user).await? else {
+        bot.send_message(
+            msg.chat.id,
+            "Usage: /ban <user id|link> [30m|12h|7d] [reason]",
+        )
+        .await?;
+        return Ok(());
+    };
+    if db.admin_role(id).await?.is_some() {
+        bot.send_message(msg.chat.id, "Admins can't be banned")
+            .await?;
+        return Ok(());
+    }
+
+    let rest = rest.trim();
+    let (first, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let (duration, reason) = match parse_duration(first) {
+        Some(duration) => (Some(duration), tail.trim()),
+        None => (None, rest),
+    };
+    let reason = (!reason.is_empty()).then(|| reason.to_owned());
+
+    let text = if db.ban_user(id, reason, duration, msg.chat.id.0).await? {
+        match db.active_ban(id).await?.and_then(|ban| ban.expires_at) {
+            Some(expires_at) => format!("{id} is banned until {expires_at} UTC"),
+            None => format!("{id} is banned permanently"),
+        }
+    } else {
+        format!("User {id} not found")
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_unban(bot: Bot, db: Arc<Db>, msg: Message, args: String) -> Result<()> {
+    let user = args.trim();
+    let Some(id) = resolve_user(&db, user).await? else {
+        bot.send_message(msg.chat.id, "Usage: /unban <user id|link>")
+            .await?;
+        return Ok(());
+    };
+    let text = if db.unban_user(id).await? {
+        format!("{id} is unbanned")
+    } else {
+        format!("{id} is not banned")
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_reclaim_link(bot: Bot, db: Arc<Db>, msg: Message, args: String) -> Result<()> {
+    let mut args = args.split_whitespace();
+    let Some(code) = args.next().map(str::to_lowercase) else {
+        bot.send_message(
+            msg.chat.id,
+            "Usage: /reclaimlink <code> [user id|link to give it to]",
+        )
+        .await?;
+        return Ok(());
+    };
+    let new_owner = match args.next() {
+        Some(user) => match resolve_user(&db, user).await? {
+            Some(id) => Some(id),
+            None => {
+                bot.send_message(msg.chat.id, format!("User {user} not found"))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let Some((owner_id, new_code)) = db.reclaim_link_code(&code).await? else {
+        bot.send_message(msg.chat.id, format!("No link with code {code}"))
+            .await?;
+        return Ok(());
+    };
+    let me = bot.get_me().await?;
+    let owner_lang = db.user_lang(owner_id, None).await?;
+    if let Err(e) = bot
+        .send_message(
+            ChatId(owner_id),
+            owner_lang.link_reclaimed(&UserLink(new_code.clone()).tme_url(&me)),
+        )
+        .await
+    {
+        warn!("can't notify {owner_id} about reclaimed link: {e}");
+    }
+
+    let mut text = format!("Link {code} taken from {owner_id}, their link is now {new_code}");
+    if let Some(new_owner) = new_owner {
+        match links::check_code(&db, &code).await? {
+            Ok(()) | Err(CodeError::Reserved) => {
+                db.get_user_link(new_owner, None).await?;
+                db.rotate_link(new_owner, links::MAX_LINK_GRACE_HOURS, Some(code.clone()))
+                    .await?;
+                text.push_str(&format!(", {code} now belongs to {new_owner}"));
+            }
+            Err(e) => text.push_str(&format!(", can't give {code} to {new_owner}: {e:?}")),
+        }
+    }
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_throttled(bot: Bot, db: Arc<Db>, msg: Message, hours: String) -> Result<()> {
+    let hours = hours.trim();
+    let Ok(hours) = (if hours.is_empty() {
+        Ok(24)
+    } else {
+        hours.parse::<u32>()
+    }) else {
+        bot.send_message(msg.chat.id, "Usage: /throttled [hours]")
+            .await?;
+        return Ok(());
+    };
+
+    let senders = db.get_throttled_senders(hours).await?;
+    let text = if senders.is_empty() {
+        format!("Nobody was throttled in the last {hours} hours")
+    } else {
+        senders
+            .iter()
+            .map(|s| {
+                format!(
+                    "{} - {} times ({}), last at {}",
+                    s.sender_id, s.count, s.scopes, s.last
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_owner_only(bot: Bot, msg: Message) -> Result<()> {
+    bot.send_message(msg.chat.id, "This command is available to owners only")
+        .await?;
+    Ok(())
+}
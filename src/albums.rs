@@ -0,0 +1,52 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use teloxide::{dispatching::UpdateHandler, prelude::*};
+use tokio::sync::Mutex;
+use tracing::*;
+
+use crate::{handle_album, MessageContext, MyDialogue};
+
+/// Telegram sends every part of an album as a separate message, all within about a second.
+const ALBUM_WINDOW: Duration = Duration::from_millis(1500);
+
+/// Parts of albums still arriving, by media group id.
+#[derive(Clone, Default)]
+pub struct Albums(Arc<Mutex<HashMap<String, Vec<Message>>>>);
+
+/// Collects album parts and handles each album once, after its last part has arrived.
+pub fn album_handler() -> UpdateHandler<anyhow::Error> {
+    dptree::filter(|msg: Message| msg.media_group_id().is_some()).endpoint(handle_album_part)
+}
+
+async fn handle_album_part(
+    ctx: MessageContext,
+    msg: Message,
+    albums: Albums,
+    dialogue: MyDialogue,
+) -> Result<()> {
+    let Some(media_group_id) = msg.media_group_id().map(str::to_owned) else {
+        return Ok(());
+    };
+    {
+        let mut pending = albums.0.lock().await;
+        let parts = pending.entry(media_group_id.clone()).or_default();
+        parts.push(msg);
+        // the first part handles the whole album
+        if parts.len() > 1 {
+            return Ok(());
+        }
+    }
+
+    tokio::spawn(async move {
+        tokio::time::sleep(ALBUM_WINDOW).await;
+        let Some(mut parts) = albums.0.lock().await.remove(&media_group_id) else {
+            return;
+        };
+        parts.sort_by_key(|part| part.id.0);
+        if let Err(e) = handle_album(ctx, parts, dialogue).await {
+            error!("can't handle album {media_group_id}: {e:?}");
+        }
+    });
+    Ok(())
+}
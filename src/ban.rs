@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use sea_orm::prelude::DateTime;
+use teloxide::{dispatching::UpdateHandler, prelude::*, types::UpdateKind};
+use tracing::*;
+
+use crate::{db::Db, i18n::Lang, Bot};
+
+#[derive(Clone, Debug)]
+pub struct Ban {
+    pub reason: Option<String>,
+    /// `None` for permanent bans.
+    pub expires_at: Option<DateTime>,
+}
+
+/// Stops processing of any update from a banned user, explaining the ban only once.
+/// Updates are dropped too when the ban can't be checked.
+pub fn banned_user_handler() -> UpdateHandler<anyhow::Error> {
+    dptree::filter_map_async(|db: Arc<Db>, upd: Update| async move {
+        let user = upd.from()?;
+        match db.active_ban(ChatId::from(user.id).0).await {
+            Ok(ban) => ban.map(Some),
+            Err(e) => {
+                error!("can't check ban of {}: {e:?}", user.id);
+                Some(None)
+            }
+        }
+    })
+    .endpoint(handle_banned)
+}
+
+async fn handle_banned(
+    bot: Bot,
+    db: Arc<Db>,
+    upd: Update,
+    ban: Option<Ban>,
+    lang: Lang,
+) -> Result<()> {
+    let text = match (ban, upd.from()) {
+        (Some(ban), Some(user)) if db.set_ban_notified(ChatId::from(user.id).0).await? => {
+            let expires_at = ban
+                .expires_at
+                .map(|t| t.format("%d.%m.%Y %H:%M UTC").to_string());
+            Some(lang.banned(ban.reason.as_deref(), expires_at.as_deref()))
+        }
+        _ => None,
+    };
+    match upd.kind {
+        UpdateKind::Message(msg) => {
+            if let Some(text) = text {
+                bot.send_message(msg.chat.id, text).await?;
+            }
+        }
+        // callback queries are answered anyway to stop the button's loading animation
+        UpdateKind::CallbackQuery(q) => match text {
+            Some(text) => {
+                bot.answer_callback_query(q.id)
+                    .show_alert(true)
+                    .text(text)
+                    .await?;
+            }
+            None => {
+                bot.answer_callback_query(q.id).await?;
+            }
+        },
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use entities::broadcasts;
+use serde::{Deserialize, Serialize};
+use teloxide::{prelude::*, types::MessageId, RequestError};
+use tracing::*;
+
+use crate::{db::Db, Bot};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const REPORT_INTERVAL: Duration = Duration::from_secs(60);
+const BATCH_SIZE: u64 = 30;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BroadcastStatus {
+    Draft,
+    Running,
+    Paused,
+    Cancelled,
+    Finished,
+}
+
+impl BroadcastStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BroadcastStatus::Draft => "draft",
+            BroadcastStatus::Running => "running",
+            BroadcastStatus::Paused => "paused",
+            BroadcastStatus::Cancelled => "cancelled",
+            BroadcastStatus::Finished => "finished",
+        }
+    }
+}
+
+impl FromStr for BroadcastStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "draft" => Ok(BroadcastStatus::Draft),
+            "running" => Ok(BroadcastStatus::Running),
+            "paused" => Ok(BroadcastStatus::Paused),
+            "cancelled" => Ok(BroadcastStatus::Cancelled),
+            "finished" => Ok(BroadcastStatus::Finished),
+            _ => bail!("unknown broadcast status: {s}"),
+        }
+    }
+}
+
+/// Filters selecting who receives a broadcast, an empty segment means everyone.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Segment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inactive_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joined_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invited_by: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl Segment {
+    pub fn of(broadcast: &broadcasts::Model) -> Result<Self> {
+        Ok(serde_json::from_value(broadcast.segment.clone())?)
+    }
+
+    pub fn describe(&self) -> String {
+        let mut filters = Vec::new();
+        if let Some(days) = self.active_days {
+            filters.push(format!("active in the last {days} days"));
+        }
+        if let Some(days) = self.inactive_days {
+            filters.push(format!("inactive for {days} days"));
+        }
+        if let Some(days) = self.joined_days {
+            filters.push(format!("joined in the last {days} days"));
+        }
+        if let Some(id) = self.invited_by {
+            filters.push(format!("referred by {id}"));
+        }
+        if let Some(language) = &self.language {
+            filters.push(format!("language {language}"));
+        }
+
+        if filters.is_empty() {
+            "all users".to_owned()
+        } else {
+            filters.join(", ")
+        }
+    }
+}
+
+/// A delivery is recorded as pending before sending, so a crash mid-send
+/// never makes the worker message the same user twice.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeliveryStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Sent => "sent",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct BroadcastStats {
+    pub sent: u64,
+    pub failed: u64,
+    pub left: u64,
+}
+
+impl BroadcastStats {
+    pub fn describe(&self, broadcast: &broadcasts::Model) -> String {
+        format!(
+            "Broadcast #{} ({}): sent {}, failed {}, left {}",
+            broadcast.id, broadcast.status, self.sent, self.failed, self.left
+        )
+    }
+}
+
+/// Delivers running broadcasts one by one, forever.
+pub async fn run_worker(bot: Bot, db: Arc<Db>) {
+    loop {
+        match db.next_running_broadcast().await {
+            Ok(Some(broadcast)) => {
+                if let Err(e) = process_broadcast(&bot, &db, broadcast).await {
+                    error!("broadcast failed: {e:?}");
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+            Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+            Err(e) => {
+                error!("can't get next broadcast: {e:?}");
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+async fn process_broadcast(bot: &Bot, db: &Db, broadcast: broadcasts::Model) -> Result<()> {
+    info!("processing broadcast #{}", broadcast.id);
+    let segment = Segment::of(&broadcast)?;
+    let mut last_report = Instant::now();
+
+    loop {
+        // pause and cancel are picked up between batches
+        let status = db.broadcast_status(broadcast.id).await?;
+        if status != BroadcastStatus::Running {
+            info!("broadcast #{} is {}", broadcast.id, status.as_str());
+            return Ok(());
+        }
+
+        let users = db
+            .broadcast_pending_users(broadcast.id, &segment, BATCH_SIZE)
+            .await?;
+        if users.is_empty() {
+            db.set_broadcast_status(
+                broadcast.id,
+                &[BroadcastStatus::Running],
+                BroadcastStatus::Finished,
+            )
+            .await?;
+            report(bot, db, broadcast.id).await?;
+            return Ok(());
+        }
+
+        for user in users {
+            if !db.claim_broadcast_delivery(broadcast.id, user).await? {
+                continue;
+            }
+            let error = send_with_retry(bot, ChatId(user), &broadcast)
+                .await
+                .err()
+                .map(|e| e.to_string());
+            db.finish_broadcast_delivery(broadcast.id, user, error)
+                .await?;
+        }
+
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            report(bot, db, broadcast.id).await?;
+            last_report = Instant::now();
+        }
+    }
+}
+
+async fn send_with_retry(
+    bot: &Bot,
+    chat_id: ChatId,
+    broadcast: &broadcasts::Model,
+) -> Result<(), RequestError> {
+    loop {
+        let res = match (broadcast.from_chat_id, broadcast.message_id) {
+            (Some(from_chat_id), Some(message_id)) => bot
+                .copy_message(chat_id, ChatId(from_chat_id), MessageId(message_id))
+                .await
+                .map(|_| ()),
+            _ => bot
+                .send_message(chat_id, broadcast.text.clone().unwrap_or_default())
+                .await
+                .map(|_| ()),
+        };
+        match res {
+            Err(RequestError::RetryAfter(secs)) => {
+                warn!("flood limit hit, waiting {}s", secs.seconds());
+                tokio::time::sleep(secs.duration()).await;
+            }
+            res => return res,
+        }
+    }
+}
+
+async fn report(bot: &Bot, db: &Db, broadcast_id: i32) -> Result<()> {
+    let broadcast = db
+        .get_broadcast(broadcast_id)
+        .await?
+        .context("broadcast not found")?;
+    let stats = db.broadcast_stats(broadcast_id).await?;
+    bot.send_message(ChatId(broadcast.created_by), stats.describe(&broadcast))
+        .await?;
+    Ok(())
+}
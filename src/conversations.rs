@@ -0,0 +1,18 @@
+/// Every delivered message belongs to a conversation between whoever used a link
+/// and its owner, replies in both directions included.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConversationStatus {
+    Open,
+    /// Closed conversations aren't continued by new messages sent via a link,
+    /// these start a new conversation instead.
+    Closed,
+}
+
+impl ConversationStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConversationStatus::Open => "open",
+            ConversationStatus::Closed => "closed",
+        }
+    }
+}
@@ -1,14 +1,38 @@
+use std::sync::Arc;
+
 use anyhow::{Context, Result};
-use entities::{messages, prelude::*, users};
-use migration::{Migrator, MigratorTrait, SimpleExpr};
+use entities::{
+    admins, blocks, broadcast_deliveries, broadcasts, conversations, deleted_link_codes, dialogues,
+    dropped_messages, filters, held_messages, links, messages, prelude::*, reports, throttles,
+    users,
+};
+use futures::future::BoxFuture;
+use migration::{Func, Migrator, MigratorTrait, OnConflict, Query, SimpleExpr};
 use rand::Rng;
 use sea_orm::{
-    prelude::*, ActiveValue, ConnectOptions, Database, DatabaseConnection, EntityTrait,
-    FromQueryResult, QuerySelect, SelectColumns,
+    prelude::*, ActiveValue, Condition, ConnectOptions, Database, DatabaseConnection, EntityTrait,
+    FromQueryResult, QueryOrder, QuerySelect, Select, SelectColumns, TransactionTrait,
+    TryInsertResult,
 };
+use teloxide::{dispatching::dialogue::Storage, types::ChatId};
 use tracing::log::LevelFilter;
 
-use crate::UserLink;
+use crate::{
+    admin::AdminRole,
+    ban::Ban,
+    broadcast::{BroadcastStats, BroadcastStatus, DeliveryStatus, Segment},
+    conversations::ConversationStatus,
+    filters::Filter,
+    i18n::Lang,
+    inbox::{Inbox, MessageType, Schedule},
+    moderation::{ReportReason, ReportStatus},
+    rate_limit::{LimitScope, ThrottledSender},
+    received::ReceivedMessage,
+    State, UserLink,
+};
+
+/// Counts an album as one message, also for its rows in `messages` and `held_messages`.
+const COUNT_MESSAGES: &str = "COUNT(DISTINCT COALESCE(media_group_id, id::text))";
 
 pub struct Db {
     dc: DatabaseConnection,
@@ -28,7 +52,7 @@ impl Db {
     }
 
     pub async fn get_user_link(&self, id: i64, invited_by: Option<i64>) -> Result<UserLink> {
-        let link = if let Some(user) = Users::find_by_id(id).one(&self.dc).await? {
+        if Users::find_by_id(id).one(&self.dc).await?.is_some() {
             Users::update_many()
                 .col_expr(
                     users::Column::LastActivity,
@@ -37,64 +61,436 @@ impl Db {
                 .filter(users::Column::Id.eq(id))
                 .exec(&self.dc)
                 .await?;
-            user.link
         } else {
-            let link: String = {
-                const CHARSET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
-                let mut rng = rand::thread_rng();
-                (0..8)
-                    .map(|_| {
-                        let idx = rng.gen_range(0..CHARSET.len());
-                        CHARSET[idx] as char
-                    })
-                    .collect()
-            };
-
             let user = users::ActiveModel {
                 id: ActiveValue::Set(id),
-                link: ActiveValue::Set(link.clone()),
                 invited_by: ActiveValue::Set(invited_by),
                 ..Default::default()
             };
             Users::insert(user).exec(&self.dc).await?;
-            link
+        }
+
+        let link = match self.main_link(id).await? {
+            Some(link) => link,
+            None => self.create_link(id, None).await?,
         };
-        Ok(UserLink(link))
+        Ok(UserLink(link.code))
+    }
+
+    /// The unlabeled link shown to the user everywhere, replaced by /newlink.
+    async fn main_link(&self, owner_id: i64) -> Result<Option<links::Model>> {
+        let link = Links::find()
+            .filter(links::Column::OwnerId.eq(owner_id))
+            .filter(links::Column::Label.is_null())
+            .filter(links::Column::DisabledAt.is_null())
+            .order_by_asc(links::Column::Id)
+            .one(&self.dc)
+            .await?;
+        Ok(link)
+    }
+
+    pub async fn create_link(&self, owner_id: i64, label: Option<String>) -> Result<links::Model> {
+        let link = Links::insert(new_link(owner_id, label))
+            .exec_with_returning(&self.dc)
+            .await?;
+        Ok(link)
+    }
+
+    /// Finds an enabled link or a disabled one still in its grace period.
+    pub async fn find_link(&self, code: &str) -> Result<Option<links::Model>> {
+        // all codes are stored lowercase, people often retype custom ones capitalized
+        let link = Links::find()
+            .filter(links::Column::Code.eq(code.to_lowercase()))
+            .filter(link_enabled())
+            .one(&self.dc)
+            .await?;
+        Ok(link)
+    }
+
+    /// Codes are never reused, so disabled links and links of deleted accounts count as taken too.
+    pub async fn link_code_taken(&self, code: &str) -> Result<bool> {
+        let count = Links::find()
+            .filter(links::Column::Code.eq(code))
+            .count(&self.dc)
+            .await?;
+        let deleted = DeletedLinkCodes::find_by_id(code).one(&self.dc).await?;
+        Ok(count > 0 || deleted.is_some())
     }
 
-    pub async fn user_id_by_link(&self, link: &str) -> Result<Option<i64>> {
-        let id = Users::find()
-            .filter(users::Column::Link.eq(link))
+    /// Gives the link with `code` a random code instead, returns its owner and the new code.
+    pub async fn reclaim_link_code(&self, code: &str) -> Result<Option<(i64, String)>> {
+        let Some(link) = Links::find()
+            .filter(links::Column::Code.eq(code))
             .one(&self.dc)
             .await?
-            .map(|u| u.id);
-        Ok(id)
+        else {
+            return Ok(None);
+        };
+        let new_code = generate_code();
+        Links::update_many()
+            .col_expr(links::Column::Code, Expr::value(new_code.clone()))
+            .filter(links::Column::Id.eq(link.id))
+            .exec(&self.dc)
+            .await?;
+        Ok(Some((link.owner_id, new_code)))
+    }
+
+    pub async fn user_id_by_link(&self, code: &str) -> Result<Option<i64>> {
+        Ok(self.find_link(code).await?.map(|l| l.owner_id))
+    }
+
+    pub async fn count_link_open(&self, link_id: i32) -> Result<()> {
+        Links::update_many()
+            .col_expr(links::Column::Opens, Expr::col(links::Column::Opens).add(1))
+            .filter(links::Column::Id.eq(link_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
     }
 
-    pub async fn save_message(
+    /// Links of the user, main link first, with the number of messages received through each.
+    /// Disabled links are only included with `with_disabled`.
+    pub async fn get_links(
+        &self,
+        owner_id: i64,
+        with_disabled: bool,
+    ) -> Result<Vec<(links::Model, u64)>> {
+        #[derive(FromQueryResult)]
+        struct LinkCount {
+            link_id: i32,
+            count: i64,
+        }
+
+        let mut query = Links::find().filter(links::Column::OwnerId.eq(owner_id));
+        if !with_disabled {
+            query = query.filter(links::Column::DisabledAt.is_null());
+        }
+        let mut links = query.order_by_asc(links::Column::Id).all(&self.dc).await?;
+        links.sort_by_key(|l| l.label.is_some());
+
+        let counts = Messages::find()
+            .select_only()
+            .select_column(messages::Column::LinkId)
+            .column_as(Expr::cust(COUNT_MESSAGES), "count")
+            .filter(messages::Column::LinkId.is_in(links.iter().map(|l| l.id)))
+            .group_by(messages::Column::LinkId)
+            .into_model::<LinkCount>()
+            .all(&self.dc)
+            .await?;
+
+        Ok(links
+            .into_iter()
+            .map(|link| {
+                let received = counts
+                    .iter()
+                    .find(|c| c.link_id == link.id)
+                    .map_or(0, |c| c.count as u64);
+                (link, received)
+            })
+            .collect())
+    }
+
+    /// Disables one of the user's labeled links, the main one can only be replaced with /newlink.
+    pub async fn disable_link(&self, owner_id: i64, link_id: i32) -> Result<bool> {
+        let res = Links::update_many()
+            .col_expr(links::Column::DisabledAt, Expr::current_timestamp().into())
+            .filter(links::Column::Id.eq(link_id))
+            .filter(links::Column::OwnerId.eq(owner_id))
+            .filter(links::Column::Label.is_not_null())
+            .filter(links::Column::DisabledAt.is_null())
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Replaces the user's main link with `code` or a random one, the old link keeps working
+    /// for `grace_hours`. Conversations are bound to user ids, so replies to old messages aren't affected.
+    pub async fn rotate_link(
+        &self,
+        user_id: i64,
+        grace_hours: u32,
+        code: Option<String>,
+    ) -> Result<UserLink> {
+        let old_link = self.main_link(user_id).await?;
+
+        let txn = self.dc.begin().await?;
+        if let Some(old_link) = old_link {
+            Links::update_many()
+                .col_expr(links::Column::DisabledAt, hours_from_now(grace_hours))
+                .filter(links::Column::Id.eq(old_link.id))
+                .exec(&txn)
+                .await?;
+        }
+        let mut link = new_link(user_id, None);
+        if let Some(code) = code {
+            link.code = ActiveValue::Set(code);
+        }
+        let link = Links::insert(link).exec_with_returning(&txn).await?;
+        txn.commit().await?;
+
+        Ok(UserLink(link.code))
+    }
+
+    /// The latest open conversation the sender started with the recipient, or a new one.
+    async fn open_conversation(
+        &self,
+        sender_id: i64,
+        recipient_id: i64,
+        link_id: Option<i32>,
+    ) -> Result<i32> {
+        let conversation = Conversations::find()
+            .filter(conversations::Column::SenderId.eq(sender_id))
+            .filter(conversations::Column::RecipientId.eq(recipient_id))
+            .filter(conversations::Column::Status.eq(ConversationStatus::Open.as_str()))
+            .order_by_desc(conversations::Column::Id)
+            .one(&self.dc)
+            .await?;
+        if let Some(conversation) = conversation {
+            return Ok(conversation.id);
+        }
+
+        let conversation = conversations::ActiveModel {
+            sender_id: ActiveValue::Set(sender_id),
+            recipient_id: ActiveValue::Set(recipient_id),
+            link_id: ActiveValue::Set(link_id),
+            status: ActiveValue::Set(ConversationStatus::Open.as_str().to_owned()),
+            ..Default::default()
+        };
+        let res = Conversations::insert(conversation).exec(&self.dc).await?;
+        Ok(res.last_insert_id)
+    }
+
+    /// Closes conversations between the two users, whoever started them.
+    async fn close_conversations(&self, user_id: i64, other_id: i64) -> Result<()> {
+        Conversations::update_many()
+            .col_expr(
+                conversations::Column::Status,
+                Expr::value(ConversationStatus::Closed.as_str()),
+            )
+            .filter(
+                Condition::any()
+                    .add(
+                        conversations::Column::SenderId
+                            .eq(user_id)
+                            .and(conversations::Column::RecipientId.eq(other_id)),
+                    )
+                    .add(
+                        conversations::Column::SenderId
+                            .eq(other_id)
+                            .and(conversations::Column::RecipientId.eq(user_id)),
+                    ),
+            )
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    /// Saves pairs of sender and recipient message ids, several pairs come from one album.
+    /// Replies continue the conversation of the replied message.
+    pub async fn save_messages(
+        &self,
+        sender_id: i64,
+        recipient_id: i64,
+        message_ids: &[(i32, i32)],
+        link_id: Option<i32>,
+        media_group_id: Option<&str>,
+        reply_to: Option<&messages::Model>,
+    ) -> Result<()> {
+        if message_ids.is_empty() {
+            return Ok(());
+        }
+        let conversation_id = match reply_to {
+            Some(replied) => replied.conversation_id,
+            None => {
+                self.open_conversation(sender_id, recipient_id, link_id)
+                    .await?
+            }
+        };
+        let messages = message_ids
+            .iter()
+            .map(
+                |&(sender_message_id, recipient_message_id)| messages::ActiveModel {
+                    sender_id: ActiveValue::Set(sender_id),
+                    sender_message_id: ActiveValue::Set(sender_message_id),
+                    recipient_id: ActiveValue::Set(recipient_id),
+                    recipient_message_id: ActiveValue::Set(recipient_message_id),
+                    link_id: ActiveValue::Set(link_id),
+                    media_group_id: ActiveValue::Set(media_group_id.map(ToOwned::to_owned)),
+                    conversation_id: ActiveValue::Set(conversation_id),
+                    reply_to: ActiveValue::Set(reply_to.map(|replied| replied.id)),
+                    ..Default::default()
+                },
+            )
+            .collect::<Vec<_>>();
+        Messages::insert_many(messages).exec(&self.dc).await?;
+        Conversations::update_many()
+            .col_expr(
+                conversations::Column::LastActivity,
+                Expr::current_timestamp().into(),
+            )
+            .filter(conversations::Column::Id.eq(conversation_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    /// Counts messages of the last `seconds` sent by `sender_id` and/or to `recipient_id`,
+    /// along with seconds until the oldest of them leaves that window. Held messages count too,
+    /// otherwise a sender tripping a hold filter could flood the recipient's review queue.
+    pub async fn recent_messages(
+        &self,
+        sender_id: Option<i64>,
+        recipient_id: Option<i64>,
+        seconds: u64,
+    ) -> Result<(u64, u64)> {
+        let mut delivered = Messages::find()
+            .filter(Expr::col(messages::Column::Timestamp).gt(seconds_ago(seconds)));
+        let mut held = HeldMessages::find()
+            .filter(Expr::col(held_messages::Column::Timestamp).gt(seconds_ago(seconds)));
+        if let Some(sender_id) = sender_id {
+            delivered = delivered.filter(messages::Column::SenderId.eq(sender_id));
+            held = held.filter(held_messages::Column::SenderId.eq(sender_id));
+        }
+        if let Some(recipient_id) = recipient_id {
+            delivered = delivered.filter(messages::Column::RecipientId.eq(recipient_id));
+            held = held.filter(held_messages::Column::RecipientId.eq(recipient_id));
+        }
+
+        let (delivered_count, delivered_wait) = self.message_window(delivered, seconds).await?;
+        let (held_count, held_wait) = self.message_window(held, seconds).await?;
+        let wait = match (delivered_wait, held_wait) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (wait, None) | (None, wait) => wait,
+        };
+        Ok((
+            delivered_count + held_count,
+            wait.map_or(0, |wait| wait.ceil().max(0.0) as u64),
+        ))
+    }
+
+    /// Counts messages selected by `query` along with seconds until the oldest of them
+    /// is `seconds` old.
+    async fn message_window<E: EntityTrait>(
+        &self,
+        query: Select<E>,
+        seconds: u64,
+    ) -> Result<(u64, Option<f64>)> {
+        #[derive(FromQueryResult)]
+        struct Window {
+            count: i64,
+            wait: Option<f64>,
+        }
+
+        let window = query
+            .select_only()
+            .column_as(Expr::cust(COUNT_MESSAGES), "count")
+            .column_as(
+                Expr::cust(format!(
+                    "EXTRACT(EPOCH FROM MIN(timestamp) + INTERVAL '{seconds} seconds' - CURRENT_TIMESTAMP)::float8"
+                )),
+                "wait",
+            )
+            .into_model::<Window>()
+            .one(&self.dc)
+            .await?
+            .context("no message count")?;
+        Ok((window.count as u64, window.wait))
+    }
+
+    pub async fn log_throttle(
         &self,
         sender_id: i64,
-        sender_message_id: i32,
         recipient_id: i64,
-        recipient_message_id: i32,
+        scope: LimitScope,
     ) -> Result<()> {
-        let message = messages::ActiveModel {
+        let throttle = throttles::ActiveModel {
             sender_id: ActiveValue::Set(sender_id),
-            sender_message_id: ActiveValue::Set(sender_message_id),
             recipient_id: ActiveValue::Set(recipient_id),
-            recipient_message_id: ActiveValue::Set(recipient_message_id),
+            scope: ActiveValue::Set(scope.as_str().to_owned()),
             ..Default::default()
         };
-        Messages::insert(message).exec(&self.dc).await?;
+        Throttles::insert(throttle).exec(&self.dc).await?;
         Ok(())
     }
 
-    pub async fn find_another_message(
+    /// Senders throttled during the last `hours`, most throttled first.
+    pub async fn get_throttled_senders(&self, hours: u32) -> Result<Vec<ThrottledSender>> {
+        let senders = Throttles::find()
+            .select_only()
+            .column(throttles::Column::SenderId)
+            .column_as(throttles::Column::Id.count(), "count")
+            .column_as(throttles::Column::Timestamp.max(), "last")
+            .column_as(Expr::cust("STRING_AGG(DISTINCT scope, ',')"), "scopes")
+            .filter(
+                Expr::col(throttles::Column::Timestamp).gt(seconds_ago(u64::from(hours) * 3600)),
+            )
+            .group_by(throttles::Column::SenderId)
+            .order_by_desc(Expr::cust("count"))
+            .limit(30)
+            .into_model::<ThrottledSender>()
+            .all(&self.dc)
+            .await?;
+        Ok(senders)
+    }
+
+    /// Messages the user received, newest first, with the first part standing for an album.
+    pub async fn received_messages(
         &self,
-        chat_id: i64,
-        msg_id: i32,
-    ) -> Result<Option<(i64, i32)>> {
-        let ids = Messages::find()
+        recipient_id: i64,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<ReceivedMessage>> {
+        let messages = Messages::find()
+            .select_only()
+            .columns([messages::Column::Id, messages::Column::Timestamp])
+            .column_as(
+                Expr::cust("(SELECT label FROM links WHERE links.id = messages.link_id)"),
+                "label",
+            )
+            .column_as(
+                Expr::cust(
+                    "EXISTS (SELECT 1 FROM messages answers \
+                    JOIN messages replied ON replied.id = answers.reply_to \
+                    WHERE answers.sender_id = messages.recipient_id \
+                    AND (replied.id = messages.id OR (replied.sender_id = messages.sender_id \
+                    AND replied.media_group_id = messages.media_group_id)))",
+                ),
+                "answered",
+            )
+            .filter(received_by(recipient_id))
+            .order_by_desc(messages::Column::Id)
+            .offset(offset)
+            .limit(limit)
+            .into_model::<ReceivedMessage>()
+            .all(&self.dc)
+            .await?;
+        Ok(messages)
+    }
+
+    pub async fn count_received(&self, recipient_id: i64) -> Result<u64> {
+        let count = Messages::find()
+            .filter(received_by(recipient_id))
+            .count(&self.dc)
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn get_received_message(
+        &self,
+        recipient_id: i64,
+        message_id: i32,
+    ) -> Result<Option<messages::Model>> {
+        let message = Messages::find_by_id(message_id)
+            .filter(messages::Column::RecipientId.eq(recipient_id))
+            .filter(messages::Column::RetractedAt.is_null())
+            .one(&self.dc)
+            .await?;
+        Ok(message)
+    }
+
+    /// A delivered message by its id in either chat.
+    pub async fn find_message(&self, chat_id: i64, msg_id: i32) -> Result<Option<messages::Model>> {
+        let message = Messages::find()
             .filter(SimpleExpr::or(
                 messages::Column::RecipientId
                     .eq(chat_id)
@@ -103,8 +499,19 @@ impl Db {
                     .eq(chat_id)
                     .and(messages::Column::SenderMessageId.eq(msg_id)),
             ))
+            .filter(messages::Column::RetractedAt.is_null())
+            .order_by_asc(messages::Column::Id)
             .one(&self.dc)
             .await?;
+        Ok(message)
+    }
+
+    pub async fn find_another_message(
+        &self,
+        chat_id: i64,
+        msg_id: i32,
+    ) -> Result<Option<(i64, i32)>> {
+        let ids = self.find_message(chat_id, msg_id).await?;
         Ok(if let Some(ids) = ids {
             if chat_id == ids.recipient_id && msg_id == ids.recipient_message_id {
                 Some((ids.sender_id, ids.sender_message_id))
@@ -116,9 +523,568 @@ impl Db {
         })
     }
 
-    pub async fn disable_answer_tip(&self, user_id: i64) -> Result<()> {
+    pub async fn find_sent_message(
+        &self,
+        sender_id: i64,
+        sender_message_id: i32,
+    ) -> Result<Option<messages::Model>> {
+        let message = Messages::find()
+            .filter(messages::Column::SenderId.eq(sender_id))
+            .filter(messages::Column::SenderMessageId.eq(sender_message_id))
+            .order_by_asc(messages::Column::Id)
+            .one(&self.dc)
+            .await?;
+        Ok(message)
+    }
+
+    /// Messages the user sent or received, oldest first.
+    pub async fn get_user_messages(&self, user_id: i64) -> Result<Vec<messages::Model>> {
+        let messages = Messages::find()
+            .filter(
+                Condition::any()
+                    .add(messages::Column::SenderId.eq(user_id))
+                    .add(messages::Column::RecipientId.eq(user_id)),
+            )
+            .order_by_asc(messages::Column::Id)
+            .all(&self.dc)
+            .await?;
+        Ok(messages)
+    }
+
+    /// Held messages the user sent or received, oldest first.
+    pub async fn get_user_held(&self, user_id: i64) -> Result<Vec<held_messages::Model>> {
+        let held = HeldMessages::find()
+            .filter(
+                Condition::any()
+                    .add(held_messages::Column::SenderId.eq(user_id))
+                    .add(held_messages::Column::RecipientId.eq(user_id)),
+            )
+            .order_by_asc(held_messages::Column::Id)
+            .all(&self.dc)
+            .await?;
+        Ok(held)
+    }
+
+    /// All saved parts of the sender's album.
+    pub async fn find_album(
+        &self,
+        sender_id: i64,
+        media_group_id: &str,
+    ) -> Result<Vec<messages::Model>> {
+        let messages = Messages::find()
+            .filter(messages::Column::SenderId.eq(sender_id))
+            .filter(messages::Column::MediaGroupId.eq(media_group_id))
+            .order_by_asc(messages::Column::Id)
+            .all(&self.dc)
+            .await?;
+        Ok(messages)
+    }
+
+    pub async fn retract_messages(&self, message_ids: Vec<i32>) -> Result<()> {
+        Messages::update_many()
+            .col_expr(
+                messages::Column::RetractedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(messages::Column::Id.is_in(message_ids))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn block_sender(&self, recipient_id: i64, sender_id: i64) -> Result<()> {
+        let block = blocks::ActiveModel {
+            recipient_id: ActiveValue::Set(recipient_id),
+            sender_id: ActiveValue::Set(sender_id),
+            ..Default::default()
+        };
+        Blocks::insert(block)
+            .on_conflict(
+                OnConflict::columns([blocks::Column::RecipientId, blocks::Column::SenderId])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .do_nothing()
+            .exec(&self.dc)
+            .await?;
+        self.close_conversations(recipient_id, sender_id).await?;
+        Ok(())
+    }
+
+    pub async fn is_blocked(&self, recipient_id: i64, sender_id: i64) -> Result<bool> {
+        let count = Blocks::find()
+            .filter(blocks::Column::RecipientId.eq(recipient_id))
+            .filter(blocks::Column::SenderId.eq(sender_id))
+            .count(&self.dc)
+            .await?;
+        Ok(count > 0)
+    }
+
+    pub async fn get_blocks(&self, recipient_id: i64) -> Result<Vec<blocks::Model>> {
+        let blocks = Blocks::find()
+            .filter(blocks::Column::RecipientId.eq(recipient_id))
+            .order_by_asc(blocks::Column::Id)
+            .all(&self.dc)
+            .await?;
+        Ok(blocks)
+    }
+
+    pub async fn unblock(&self, recipient_id: i64, block_id: i32) -> Result<bool> {
+        let res = Blocks::delete_many()
+            .filter(blocks::Column::Id.eq(block_id))
+            .filter(blocks::Column::RecipientId.eq(recipient_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn get_filters(&self, owner_id: i64) -> Result<Vec<filters::Model>> {
+        let filters = Filters::find()
+            .filter(filters::Column::OwnerId.eq(owner_id))
+            .order_by_asc(filters::Column::Id)
+            .all(&self.dc)
+            .await?;
+        Ok(filters)
+    }
+
+    pub async fn add_filter(&self, owner_id: i64, filter: &Filter) -> Result<()> {
+        let filter = filters::ActiveModel {
+            owner_id: ActiveValue::Set(owner_id),
+            kind: ActiveValue::Set(filter.kind.as_str().to_owned()),
+            pattern: ActiveValue::Set(filter.pattern.clone()),
+            action: ActiveValue::Set(filter.action.as_str().to_owned()),
+            ..Default::default()
+        };
+        Filters::insert(filter).exec(&self.dc).await?;
+        Ok(())
+    }
+
+    pub async fn remove_filter(&self, owner_id: i64, filter_id: i32) -> Result<bool> {
+        let res = Filters::delete_many()
+            .filter(filters::Column::Id.eq(filter_id))
+            .filter(filters::Column::OwnerId.eq(owner_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// `reply_to` is the message in the recipient's chat the held message answers,
+    /// `reason` is the filter that held it.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn hold_message(
+        &self,
+        sender_id: i64,
+        sender_message_id: i32,
+        recipient_id: i64,
+        reply_to: Option<i32>,
+        link_id: Option<i32>,
+        reason: &str,
+        media_group_id: Option<&str>,
+    ) -> Result<()> {
+        let held = held_messages::ActiveModel {
+            sender_id: ActiveValue::Set(sender_id),
+            sender_message_id: ActiveValue::Set(sender_message_id),
+            recipient_id: ActiveValue::Set(recipient_id),
+            reply_to: ActiveValue::Set(reply_to),
+            link_id: ActiveValue::Set(link_id),
+            reason: ActiveValue::Set(reason.to_owned()),
+            media_group_id: ActiveValue::Set(media_group_id.map(ToOwned::to_owned)),
+            ..Default::default()
+        };
+        HeldMessages::insert(held).exec(&self.dc).await?;
+        Ok(())
+    }
+
+    pub async fn drop_messages(
+        &self,
+        sender_id: i64,
+        recipient_id: i64,
+        sender_message_ids: &[i32],
+    ) -> Result<()> {
+        let dropped =
+            sender_message_ids
+                .iter()
+                .map(|&sender_message_id| dropped_messages::ActiveModel {
+                    sender_id: ActiveValue::Set(sender_id),
+                    sender_message_id: ActiveValue::Set(sender_message_id),
+                    recipient_id: ActiveValue::Set(recipient_id),
+                    ..Default::default()
+                });
+        DroppedMessages::insert_many(dropped).exec(&self.dc).await?;
+        Ok(())
+    }
+
+    /// Forgets a dropped message the sender retracted, returns false if there is no such message.
+    pub async fn remove_dropped(&self, sender_id: i64, sender_message_id: i32) -> Result<bool> {
+        let res = DroppedMessages::delete_many()
+            .filter(dropped_messages::Column::SenderId.eq(sender_id))
+            .filter(dropped_messages::Column::SenderMessageId.eq(sender_message_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Remembers the copies shown to the recipient for review, as pairs of held part and copy ids.
+    pub async fn set_held_previews(&self, previews: &[(i32, i32)]) -> Result<()> {
+        for &(held_id, preview_message_id) in previews {
+            HeldMessages::update_many()
+                .col_expr(
+                    held_messages::Column::PreviewMessageId,
+                    Expr::value(preview_message_id),
+                )
+                .filter(held_messages::Column::Id.eq(held_id))
+                .exec(&self.dc)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn count_held(&self, recipient_id: i64) -> Result<u64> {
+        let count = HeldMessages::find()
+            .select_only()
+            .column_as(Expr::cust(COUNT_MESSAGES), "count")
+            .filter(held_messages::Column::RecipientId.eq(recipient_id))
+            .into_tuple::<i64>()
+            .one(&self.dc)
+            .await?
+            .context("no held message count")?;
+        Ok(count as u64)
+    }
+
+    /// The oldest held message with the rest of its album, along with the number of held messages.
+    pub async fn next_held(
+        &self,
+        recipient_id: i64,
+    ) -> Result<Option<(Vec<held_messages::Model>, u64)>> {
+        let Some(held) = HeldMessages::find()
+            .filter(held_messages::Column::RecipientId.eq(recipient_id))
+            .order_by_asc(held_messages::Column::Id)
+            .one(&self.dc)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let parts = self.held_album(&held).await?;
+        let count = self.count_held(recipient_id).await?;
+        Ok(Some((parts, count)))
+    }
+
+    /// All held parts of the album the held message belongs to, or just the message.
+    async fn held_album(&self, held: &held_messages::Model) -> Result<Vec<held_messages::Model>> {
+        let Some(media_group_id) = &held.media_group_id else {
+            return Ok(vec![held.clone()]);
+        };
+        let parts = HeldMessages::find()
+            .filter(held_messages::Column::SenderId.eq(held.sender_id))
+            .filter(held_messages::Column::MediaGroupId.eq(media_group_id))
+            .order_by_asc(held_messages::Column::SenderMessageId)
+            .all(&self.dc)
+            .await?;
+        Ok(parts)
+    }
+
+    /// Removes a message the sender retracted before the recipient reviewed it,
+    /// along with the rest of its album.
+    pub async fn remove_held(&self, sender_id: i64, sender_message_id: i32) -> Result<bool> {
+        let Some(held) = HeldMessages::find()
+            .filter(held_messages::Column::SenderId.eq(sender_id))
+            .filter(held_messages::Column::SenderMessageId.eq(sender_message_id))
+            .one(&self.dc)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let same_message = match held.media_group_id {
+            Some(media_group_id) => held_messages::Column::MediaGroupId.eq(media_group_id),
+            None => held_messages::Column::Id.eq(held.id),
+        };
+        let res = HeldMessages::delete_many()
+            .filter(held_messages::Column::SenderId.eq(sender_id))
+            .filter(same_message)
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Removes a held message with the rest of its album, returning the parts
+    /// unless they were already reviewed.
+    pub async fn take_held(
+        &self,
+        recipient_id: i64,
+        held_id: i32,
+    ) -> Result<Vec<held_messages::Model>> {
+        let Some(held) = HeldMessages::find_by_id(held_id)
+            .filter(held_messages::Column::RecipientId.eq(recipient_id))
+            .one(&self.dc)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        let parts = self.held_album(&held).await?;
+        let res = HeldMessages::delete_many()
+            .filter(held_messages::Column::Id.is_in(parts.iter().map(|p| p.id)))
+            .exec(&self.dc)
+            .await?;
+        Ok(if res.rows_affected > 0 {
+            parts
+        } else {
+            Vec::new()
+        })
+    }
+
+    /// Returns `None` if the reporter has already reported this message.
+    pub async fn create_report(
+        &self,
+        reporter_id: i64,
+        reporter_message_id: i32,
+        sender_id: i64,
+        sender_message_id: i32,
+        reason: ReportReason,
+    ) -> Result<Option<i32>> {
+        let existing = Reports::find()
+            .filter(reports::Column::ReporterId.eq(reporter_id))
+            .filter(reports::Column::ReporterMessageId.eq(reporter_message_id))
+            .count(&self.dc)
+            .await?;
+        if existing > 0 {
+            return Ok(None);
+        }
+
+        let report = reports::ActiveModel {
+            reporter_id: ActiveValue::Set(reporter_id),
+            reporter_message_id: ActiveValue::Set(reporter_message_id),
+            sender_id: ActiveValue::Set(sender_id),
+            sender_message_id: ActiveValue::Set(sender_message_id),
+            reason: ActiveValue::Set(reason.as_str().to_owned()),
+            status: ActiveValue::Set(ReportStatus::Open.as_str().to_owned()),
+            ..Default::default()
+        };
+        let res = Reports::insert(report).exec(&self.dc).await?;
+        Ok(Some(res.last_insert_id))
+    }
+
+    pub async fn get_report(&self, id: i32) -> Result<Option<reports::Model>> {
+        Ok(Reports::find_by_id(id).one(&self.dc).await?)
+    }
+
+    /// Reports the user filed, oldest first.
+    pub async fn get_user_reports(&self, reporter_id: i64) -> Result<Vec<reports::Model>> {
+        let reports = Reports::find()
+            .filter(reports::Column::ReporterId.eq(reporter_id))
+            .order_by_asc(reports::Column::Id)
+            .all(&self.dc)
+            .await?;
+        Ok(reports)
+    }
+
+    /// Records a moderator's decision, returns false if the report isn't open anymore.
+    pub async fn decide_report(
+        &self,
+        id: i32,
+        decision: ReportStatus,
+        moderator_id: i64,
+    ) -> Result<bool> {
+        let res = Reports::update_many()
+            .col_expr(reports::Column::Status, Expr::value(decision.as_str()))
+            .col_expr(reports::Column::ModeratorId, Expr::value(moderator_id))
+            .col_expr(reports::Column::DecidedAt, Expr::current_timestamp().into())
+            .filter(reports::Column::Id.eq(id))
+            .filter(reports::Column::Status.eq(ReportStatus::Open.as_str()))
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Returns the user's ban unless there is none or it has already expired.
+    pub async fn active_ban(&self, user_id: i64) -> Result<Option<Ban>> {
+        let user = Users::find_by_id(user_id)
+            .filter(users::Column::BannedAt.is_not_null())
+            .filter(
+                Condition::any()
+                    .add(users::Column::BanExpiresAt.is_null())
+                    .add(Expr::col(users::Column::BanExpiresAt).gt(Expr::current_timestamp())),
+            )
+            .one(&self.dc)
+            .await?;
+        Ok(user.map(|u| Ban {
+            reason: u.ban_reason,
+            expires_at: u.ban_expires_at,
+        }))
+    }
+
+    /// Bans the user for `duration` seconds or forever, returns false if there is no such user.
+    pub async fn ban_user(
+        &self,
+        user_id: i64,
+        reason: Option<String>,
+        duration: Option<u64>,
+        banned_by: i64,
+    ) -> Result<bool> {
+        let expires_at = match duration {
+            Some(secs) => Expr::cust(format!("CURRENT_TIMESTAMP + INTERVAL '{secs} seconds'")),
+            None => Expr::value(Option::<DateTime>::None),
+        };
+        let res = Users::update_many()
+            .col_expr(users::Column::BannedAt, Expr::current_timestamp().into())
+            .col_expr(users::Column::BanExpiresAt, expires_at)
+            .col_expr(users::Column::BanReason, Expr::value(reason))
+            .col_expr(users::Column::BannedBy, Expr::value(banned_by))
+            .col_expr(
+                users::Column::BanNotifiedAt,
+                Expr::value(Option::<DateTime>::None),
+            )
+            .filter(users::Column::Id.eq(user_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn unban_user(&self, user_id: i64) -> Result<bool> {
+        let res = Users::update_many()
+            .col_expr(
+                users::Column::BannedAt,
+                Expr::value(Option::<DateTime>::None),
+            )
+            .col_expr(
+                users::Column::BanExpiresAt,
+                Expr::value(Option::<DateTime>::None),
+            )
+            .col_expr(
+                users::Column::BanReason,
+                Expr::value(Option::<String>::None),
+            )
+            .col_expr(users::Column::BannedBy, Expr::value(Option::<i64>::None))
+            .col_expr(
+                users::Column::BanNotifiedAt,
+                Expr::value(Option::<DateTime>::None),
+            )
+            .filter(users::Column::Id.eq(user_id))
+            .filter(users::Column::BannedAt.is_not_null())
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Marks the user's ban as explained, returns false if it already was.
+    pub async fn set_ban_notified(&self, user_id: i64) -> Result<bool> {
+        let res = Users::update_many()
+            .col_expr(
+                users::Column::BanNotifiedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(users::Column::Id.eq(user_id))
+            .filter(users::Column::BanNotifiedAt.is_null())
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn get_user(&self, user_id: i64) -> Result<Option<users::Model>> {
+        Ok(Users::find_by_id(user_id).one(&self.dc).await?)
+    }
+
+    /// Erases the user along with everything referencing them, in the order foreign keys allow.
+    /// Messages are removed for both sides, so replies to them find nothing, and users the deleted
+    /// user invited are no longer attributed to anyone.
+    pub async fn delete_user(&self, user_id: i64) -> Result<()> {
+        let txn = self.dc.begin().await?;
+        Messages::delete_many()
+            .filter(
+                Condition::any()
+                    .add(messages::Column::SenderId.eq(user_id))
+                    .add(messages::Column::RecipientId.eq(user_id)),
+            )
+            .exec(&txn)
+            .await?;
+        HeldMessages::delete_many()
+            .filter(
+                Condition::any()
+                    .add(held_messages::Column::SenderId.eq(user_id))
+                    .add(held_messages::Column::RecipientId.eq(user_id)),
+            )
+            .exec(&txn)
+            .await?;
+        Conversations::delete_many()
+            .filter(
+                Condition::any()
+                    .add(conversations::Column::SenderId.eq(user_id))
+                    .add(conversations::Column::RecipientId.eq(user_id)),
+            )
+            .exec(&txn)
+            .await?;
+        // reports against the user are kept for moderators, only the ones they filed go
+        Reports::delete_many()
+            .filter(reports::Column::ReporterId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        Blocks::delete_many()
+            .filter(
+                Condition::any()
+                    .add(blocks::Column::RecipientId.eq(user_id))
+                    .add(blocks::Column::SenderId.eq(user_id)),
+            )
+            .exec(&txn)
+            .await?;
+        Throttles::delete_many()
+            .filter(
+                Condition::any()
+                    .add(throttles::Column::SenderId.eq(user_id))
+                    .add(throttles::Column::RecipientId.eq(user_id)),
+            )
+            .exec(&txn)
+            .await?;
+        DroppedMessages::delete_many()
+            .filter(
+                Condition::any()
+                    .add(dropped_messages::Column::SenderId.eq(user_id))
+                    .add(dropped_messages::Column::RecipientId.eq(user_id)),
+            )
+            .exec(&txn)
+            .await?;
+        Filters::delete_many()
+            .filter(filters::Column::OwnerId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        BroadcastDeliveries::delete_many()
+            .filter(broadcast_deliveries::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        // the codes stay reserved so that nobody can pick up the deleted user's old links
+        let codes = Links::find()
+            .filter(links::Column::OwnerId.eq(user_id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|link| deleted_link_codes::ActiveModel {
+                code: ActiveValue::Set(link.code),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        if !codes.is_empty() {
+            DeletedLinkCodes::insert_many(codes).exec(&txn).await?;
+        }
+        Links::delete_many()
+            .filter(links::Column::OwnerId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        Dialogues::delete_many()
+            .filter(dialogues::Column::ChatId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        Admins::delete_by_id(user_id).exec(&txn).await?;
+        Users::update_many()
+            .col_expr(users::Column::InvitedBy, Expr::value(Option::<i64>::None))
+            .filter(users::Column::InvitedBy.eq(user_id))
+            .exec(&txn)
+            .await?;
+        Users::delete_by_id(user_id).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn set_answer_tip(&self, user_id: i64, enabled: bool) -> Result<()> {
         Users::update_many()
-            .col_expr(users::Column::AnswerTip, Expr::value(false))
+            .col_expr(users::Column::AnswerTip, Expr::value(enabled))
             .filter(users::Column::Id.eq(user_id))
             .exec(&self.dc)
             .await?;
@@ -133,19 +1099,510 @@ impl Db {
         Ok(user.answer_tip)
     }
 
-    pub async fn get_all_users(&self) -> Result<Vec<i64>> {
+    /// Returns user's language, remembering `language_code` reported by Telegram if given.
+    pub async fn user_lang(&self, user_id: i64, language_code: Option<&str>) -> Result<Lang> {
+        let Some(user) = Users::find_by_id(user_id).one(&self.dc).await? else {
+            return Ok(Lang::detect(language_code));
+        };
+        if language_code.is_some() && language_code != user.language_code.as_deref() {
+            Users::update_many()
+                .col_expr(users::Column::LanguageCode, Expr::value(language_code))
+                .filter(users::Column::Id.eq(user_id))
+                .exec(&self.dc)
+                .await?;
+        }
+
+        let lang = user
+            .language
+            .as_deref()
+            .and_then(Lang::from_code)
+            .unwrap_or_else(|| Lang::detect(language_code.or(user.language_code.as_deref())));
+        Ok(lang)
+    }
+
+    pub async fn set_language(&self, user_id: i64, lang: Lang) -> Result<()> {
+        Users::update_many()
+            .col_expr(users::Column::Language, Expr::value(lang.code()))
+            .filter(users::Column::Id.eq(user_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_inbox(&self, user_id: i64) -> Result<Inbox> {
+        let Some(user) = Users::find_by_id(user_id).one(&self.dc).await? else {
+            return Ok(Inbox::default());
+        };
+        Ok(Inbox {
+            paused: user.paused,
+            note: user.closed_note,
+            schedule: user.schedule.map(serde_json::from_value).transpose()?,
+            accepted_types: user
+                .accepted_types
+                .map(serde_json::from_value)
+                .transpose()?,
+            max_text_length: user.max_text_length.map(|max| max as u32),
+        })
+    }
+
+    pub async fn set_paused(&self, user_id: i64, paused: bool, note: Option<String>) -> Result<()> {
+        Users::update_many()
+            .col_expr(users::Column::Paused, Expr::value(paused))
+            .col_expr(users::Column::ClosedNote, Expr::value(note))
+            .filter(users::Column::Id.eq(user_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_accepted_types(
+        &self,
+        user_id: i64,
+        types: Option<&[MessageType]>,
+    ) -> Result<()> {
+        let types = types.map(serde_json::to_value).transpose()?;
+        Users::update_many()
+            .col_expr(users::Column::AcceptedTypes, Expr::value(types))
+            .filter(users::Column::Id.eq(user_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    /// The user's own retention period, if shorter than the global one.
+    pub async fn retention_days(&self, user_id: i64) -> Result<Option<u32>> {
+        let user = Users::find_by_id(user_id).one(&self.dc).await?;
+        Ok(user
+            .and_then(|user| user.retention_days)
+            .map(|days| days as u32))
+    }
+
+    pub async fn set_retention_days(&self, user_id: i64, days: Option<u32>) -> Result<()> {
+        Users::update_many()
+            .col_expr(
+                users::Column::RetentionDays,
+                Expr::value(days.map(|days| days as i32)),
+            )
+            .filter(users::Column::Id.eq(user_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes messages older than the shortest retention period of their sender, their recipient
+    /// and `max_days`, then conversations left empty, returning how many of each were deleted.
+    pub async fn purge_messages(&self, max_days: u32) -> Result<(u64, u64)> {
+        let expired = |table: &str| {
+            Expr::cust(format!(
+                "{table}.timestamp < CURRENT_TIMESTAMP - make_interval(days => LEAST({max_days}, \
+                COALESCE((SELECT retention_days FROM users WHERE users.id = {table}.sender_id), {max_days}), \
+                COALESCE((SELECT retention_days FROM users WHERE users.id = {table}.recipient_id), {max_days})))"
+            ))
+        };
+        let messages = Messages::delete_many()
+            .filter(expired("messages"))
+            .exec(&self.dc)
+            .await?;
+        // dropped messages go at the same time, unsending them must look the same
+        DroppedMessages::delete_many()
+            .filter(expired("dropped_messages"))
+            .exec(&self.dc)
+            .await?;
+        // a day is plenty for a new conversation to get its first message
+        let conversations = Conversations::delete_many()
+            .filter(Expr::cust(
+                "NOT EXISTS (SELECT 1 FROM messages WHERE messages.conversation_id = conversations.id)",
+            ))
+            .filter(Expr::col(conversations::Column::LastActivity).lt(days_ago(1)))
+            .exec(&self.dc)
+            .await?;
+        Ok((messages.rows_affected, conversations.rows_affected))
+    }
+
+    pub async fn set_max_text_length(&self, user_id: i64, max: Option<u32>) -> Result<()> {
+        Users::update_many()
+            .col_expr(
+                users::Column::MaxTextLength,
+                Expr::value(max.map(|max| max as i32)),
+            )
+            .filter(users::Column::Id.eq(user_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_schedule(&self, user_id: i64, schedule: Option<&Schedule>) -> Result<()> {
+        let schedule = schedule.map(serde_json::to_value).transpose()?;
+        Users::update_many()
+            .col_expr(users::Column::Schedule, Expr::value(schedule))
+            .filter(users::Column::Id.eq(user_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn admin_role(&self, user_id: i64) -> Result<Option<AdminRole>> {
+        let role = match Admins::find_by_id(user_id).one(&self.dc).await? {
+            Some(admin) => Some(admin.role.parse()?),
+            None => None,
+        };
+        Ok(role)
+    }
+
+    pub async fn get_admins(&self) -> Result<Vec<(i64, AdminRole)>> {
+        Admins::find()
+            .all(&self.dc)
+            .await?
+            .into_iter()
+            .map(|a| Ok((a.id, a.role.parse()?)))
+            .collect()
+    }
+
+    pub async fn add_admin(&self, id: i64, role: AdminRole, added_by: Option<i64>) -> Result<()> {
+        let admin = admins::ActiveModel {
+            id: ActiveValue::Set(id),
+            role: ActiveValue::Set(role.as_str().to_owned()),
+            added_by: ActiveValue::Set(added_by),
+            ..Default::default()
+        };
+        Admins::insert(admin)
+            .on_conflict(
+                OnConflict::column(admins::Column::Id)
+                    .update_columns([admins::Column::Role, admins::Column::AddedBy])
+                    .to_owned(),
+            )
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_admin(&self, id: i64) -> Result<bool> {
+        let res = Admins::delete_by_id(id).exec(&self.dc).await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Creates a draft broadcast of either `text` or a copy of `message` (chat id, message id).
+    pub async fn create_broadcast(
+        &self,
+        created_by: i64,
+        text: Option<String>,
+        message: Option<(i64, i32)>,
+        segment: &Segment,
+    ) -> Result<i32> {
+        let broadcast = broadcasts::ActiveModel {
+            created_by: ActiveValue::Set(created_by),
+            text: ActiveValue::Set(text),
+            from_chat_id: ActiveValue::Set(message.map(|m| m.0)),
+            message_id: ActiveValue::Set(message.map(|m| m.1)),
+            segment: ActiveValue::Set(serde_json::to_value(segment)?),
+            status: ActiveValue::Set(BroadcastStatus::Draft.as_str().to_owned()),
+            ..Default::default()
+        };
+        let res = Broadcasts::insert(broadcast).exec(&self.dc).await?;
+        Ok(res.last_insert_id)
+    }
+
+    pub async fn get_broadcast(&self, id: i32) -> Result<Option<broadcasts::Model>> {
+        Ok(Broadcasts::find_by_id(id).one(&self.dc).await?)
+    }
+
+    pub async fn get_last_broadcasts(&self, limit: u64) -> Result<Vec<broadcasts::Model>> {
+        let broadcasts = Broadcasts::find()
+            .order_by_desc(broadcasts::Column::Id)
+            .limit(limit)
+            .all(&self.dc)
+            .await?;
+        Ok(broadcasts)
+    }
+
+    pub async fn broadcast_status(&self, id: i32) -> Result<BroadcastStatus> {
+        let broadcast = self
+            .get_broadcast(id)
+            .await?
+            .context("broadcast not found")?;
+        broadcast.status.parse()
+    }
+
+    pub async fn next_running_broadcast(&self) -> Result<Option<broadcasts::Model>> {
+        let broadcast = Broadcasts::find()
+            .filter(broadcasts::Column::Status.eq(BroadcastStatus::Running.as_str()))
+            .order_by_asc(broadcasts::Column::Id)
+            .one(&self.dc)
+            .await?;
+        Ok(broadcast)
+    }
+
+    /// Moves a broadcast to `to` if it is currently in one of `from` statuses.
+    pub async fn set_broadcast_status(
+        &self,
+        id: i32,
+        from: &[BroadcastStatus],
+        to: BroadcastStatus,
+    ) -> Result<bool> {
+        let res = Broadcasts::update_many()
+            .col_expr(broadcasts::Column::Status, Expr::value(to.as_str()))
+            .filter(broadcasts::Column::Id.eq(id))
+            .filter(broadcasts::Column::Status.is_in(from.iter().map(|s| s.as_str())))
+            .exec(&self.dc)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn count_segment(&self, segment: &Segment) -> Result<u64> {
+        Ok(Users::find()
+            .filter(segment_condition(segment))
+            .count(&self.dc)
+            .await?)
+    }
+
+    /// Users of the segment that weren't attempted yet by the broadcast.
+    pub async fn broadcast_pending_users(
+        &self,
+        id: i32,
+        segment: &Segment,
+        limit: u64,
+    ) -> Result<Vec<i64>> {
         #[derive(FromQueryResult)]
         struct UserWithId {
             id: i64,
         }
 
-        let users = Users::find()
+        let users = broadcast_pending_users(id, segment)
             .select_only()
             .select_column(users::Column::Id)
+            .order_by_asc(users::Column::Id)
+            .limit(limit)
             .into_model::<UserWithId>()
             .all(&self.dc)
             .await?;
 
         Ok(users.into_iter().map(|u| u.id).collect())
     }
+
+    /// Returns false if someone has already attempted delivering to this user.
+    pub async fn claim_broadcast_delivery(&self, broadcast_id: i32, user_id: i64) -> Result<bool> {
+        let delivery = broadcast_deliveries::ActiveModel {
+            broadcast_id: ActiveValue::Set(broadcast_id),
+            user_id: ActiveValue::Set(user_id),
+            status: ActiveValue::Set(DeliveryStatus::Pending.as_str().to_owned()),
+            ..Default::default()
+        };
+        let res = BroadcastDeliveries::insert(delivery)
+            .on_conflict_do_nothing()
+            .exec(&self.dc)
+            .await?;
+        Ok(matches!(res, TryInsertResult::Inserted(_)))
+    }
+
+    pub async fn finish_broadcast_delivery(
+        &self,
+        broadcast_id: i32,
+        user_id: i64,
+        error: Option<String>,
+    ) -> Result<()> {
+        let status = match error {
+            Some(_) => DeliveryStatus::Failed,
+            None => DeliveryStatus::Sent,
+        };
+        BroadcastDeliveries::update_many()
+            .col_expr(
+                broadcast_deliveries::Column::Status,
+                Expr::value(status.as_str()),
+            )
+            .col_expr(broadcast_deliveries::Column::Error, Expr::value(error))
+            .col_expr(
+                broadcast_deliveries::Column::Timestamp,
+                Expr::current_timestamp().into(),
+            )
+            .filter(broadcast_deliveries::Column::BroadcastId.eq(broadcast_id))
+            .filter(broadcast_deliveries::Column::UserId.eq(user_id))
+            .exec(&self.dc)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn broadcast_stats(&self, id: i32) -> Result<BroadcastStats> {
+        #[derive(FromQueryResult)]
+        struct StatusCount {
+            status: String,
+            count: i64,
+        }
+
+        let counts = BroadcastDeliveries::find()
+            .select_only()
+            .select_column(broadcast_deliveries::Column::Status)
+            .column_as(broadcast_deliveries::Column::UserId.count(), "count")
+            .filter(broadcast_deliveries::Column::BroadcastId.eq(id))
+            .group_by(broadcast_deliveries::Column::Status)
+            .into_model::<StatusCount>()
+            .all(&self.dc)
+            .await?;
+
+        let mut stats = BroadcastStats::default();
+        for c in counts {
+            if c.status == DeliveryStatus::Sent.as_str() {
+                stats.sent = c.count as u64;
+            } else if c.status == DeliveryStatus::Failed.as_str() {
+                stats.failed = c.count as u64;
+            }
+        }
+
+        let broadcast = self
+            .get_broadcast(id)
+            .await?
+            .context("broadcast not found")?;
+        stats.left = broadcast_pending_users(id, &Segment::of(&broadcast)?)
+            .count(&self.dc)
+            .await?;
+        Ok(stats)
+    }
+}
+
+impl Storage<State> for Db {
+    type Error = DbErr;
+
+    fn remove_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+    ) -> BoxFuture<'static, Result<(), Self::Error>> {
+        Box::pin(async move {
+            Dialogues::delete_by_id(chat_id.0).exec(&self.dc).await?;
+            Ok(())
+        })
+    }
+
+    fn update_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+        dialogue: State,
+    ) -> BoxFuture<'static, Result<(), Self::Error>> {
+        Box::pin(async move {
+            // State::Start is what get_or_default returns anyway, no need to keep a row for it
+            if let State::Start = dialogue {
+                return self.remove_dialogue(chat_id).await;
+            }
+
+            let state = serde_json::to_value(&dialogue).map_err(|e| DbErr::Json(e.to_string()))?;
+            let dialogue = dialogues::ActiveModel {
+                chat_id: ActiveValue::Set(chat_id.0),
+                state: ActiveValue::Set(state),
+                ..Default::default()
+            };
+            Dialogues::insert(dialogue)
+                .on_conflict(
+                    OnConflict::column(dialogues::Column::ChatId)
+                        .update_column(dialogues::Column::State)
+                        .value(dialogues::Column::UpdatedAt, Expr::current_timestamp())
+                        .to_owned(),
+                )
+                .exec(&self.dc)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn get_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+    ) -> BoxFuture<'static, Result<Option<State>, Self::Error>> {
+        Box::pin(async move {
+            let Some(dialogue) = Dialogues::find_by_id(chat_id.0).one(&self.dc).await? else {
+                return Ok(None);
+            };
+            let state =
+                serde_json::from_value(dialogue.state).map_err(|e| DbErr::Json(e.to_string()))?;
+            Ok(Some(state))
+        })
+    }
+}
+
+fn generate_code() -> String {
+    const CHARSET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = rand::thread_rng();
+    (0..8)
+        .map(|_| {
+            let idx = rng.gen_range(0..CHARSET.len());
+            CHARSET[idx] as char
+        })
+        .collect()
+}
+
+fn new_link(owner_id: i64, label: Option<String>) -> links::ActiveModel {
+    links::ActiveModel {
+        owner_id: ActiveValue::Set(owner_id),
+        code: ActiveValue::Set(generate_code()),
+        label: ActiveValue::Set(label),
+        ..Default::default()
+    }
+}
+
+/// Links that aren't disabled or are still in their grace period.
+fn link_enabled() -> Condition {
+    Condition::any()
+        .add(links::Column::DisabledAt.is_null())
+        .add(Expr::col(links::Column::DisabledAt).gt(Expr::current_timestamp()))
+}
+
+/// Messages the user received and still has, albums by their first part only.
+fn received_by(recipient_id: i64) -> Condition {
+    Condition::all()
+        .add(messages::Column::RecipientId.eq(recipient_id))
+        .add(messages::Column::RetractedAt.is_null())
+        .add(Expr::cust(
+            "(messages.media_group_id IS NULL OR messages.id = \
+            (SELECT MIN(parts.id) FROM messages parts \
+            WHERE parts.sender_id = messages.sender_id \
+            AND parts.media_group_id = messages.media_group_id))",
+        ))
+}
+
+fn hours_from_now(hours: u32) -> SimpleExpr {
+    Expr::cust(format!("CURRENT_TIMESTAMP + INTERVAL '{hours} hours'"))
+}
+
+fn seconds_ago(seconds: u64) -> SimpleExpr {
+    Expr::cust(format!("CURRENT_TIMESTAMP - INTERVAL '{seconds} seconds'"))
+}
+
+fn days_ago(days: u32) -> SimpleExpr {
+    Expr::cust(format!("CURRENT_TIMESTAMP - INTERVAL '{days} days'"))
+}
+
+fn segment_condition(segment: &Segment) -> Condition {
+    let mut condition = Condition::all();
+    if let Some(days) = segment.active_days {
+        condition = condition.add(Expr::col(users::Column::LastActivity).gte(days_ago(days)));
+    }
+    if let Some(days) = segment.inactive_days {
+        condition = condition.add(Expr::col(users::Column::LastActivity).lt(days_ago(days)));
+    }
+    if let Some(days) = segment.joined_days {
+        condition = condition.add(Expr::col(users::Column::FirstActivity).gte(days_ago(days)));
+    }
+    if let Some(invited_by) = segment.invited_by {
+        condition = condition.add(users::Column::InvitedBy.eq(invited_by));
+    }
+    if let Some(language) = &segment.language {
+        // explicitly chosen language first, then the one reported by Telegram
+        condition = condition.add(
+            Expr::expr(Func::coalesce([
+                Expr::col(users::Column::Language).into(),
+                Expr::col(users::Column::LanguageCode).into(),
+            ]))
+            .like(format!("{language}%")),
+        );
+    }
+    condition
+}
+
+fn broadcast_pending_users(id: i32, segment: &Segment) -> Select<Users> {
+    Users::find().filter(segment_condition(segment)).filter(
+        users::Column::Id.not_in_subquery(
+            Query::select()
+                .column(broadcast_deliveries::Column::UserId)
+                .from(BroadcastDeliveries)
+                .and_where(broadcast_deliveries::Column::BroadcastId.eq(id))
+                .to_owned(),
+        ),
+    )
 }
@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use teloxide::{
+    dispatching::UpdateHandler,
+    prelude::*,
+    types::{
+        InlineKeyboardMarkup, InputFile, InputMedia, InputMediaAnimation, InputMediaAudio,
+        InputMediaDocument, InputMediaPhoto, InputMediaVideo, MessageEntity, MessageId,
+    },
+};
+use tracing::*;
+
+use crate::{
+    db::Db,
+    filters::{self, FilterAction},
+    message_keyboard, Bot,
+};
+
+const MAX_TEXT_LENGTH: usize = 4096;
+const MAX_CAPTION_LENGTH: usize = 1024;
+
+/// Mirrors edits of delivered messages, in both directions, to their copies.
+pub fn edited_message_handler() -> UpdateHandler<anyhow::Error> {
+    Update::filter_edited_message().endpoint(handle_edited_message)
+}
+
+async fn handle_edited_message(bot: Bot, db: Arc<Db>, msg: Message) -> Result<()> {
+    let Some((chat_id, message_id)) = db.find_another_message(msg.chat.id.0, msg.id.0).await?
+    else {
+        return Ok(());
+    };
+    let (chat_id, message_id) = (ChatId(chat_id), MessageId(message_id));
+    if db.is_blocked(chat_id.0, msg.chat.id.0).await? {
+        return Ok(());
+    }
+    // the edited version goes through the same checks as a new message, failing them
+    // leaves the copy as it was
+    // replies aren't subject to the recipient's inbox settings
+    if msg.reply_to_message().is_none() && db.get_inbox(chat_id.0).await?.rejects(&msg).is_some() {
+        return Ok(());
+    }
+    let filter = filters::check(&db, chat_id.0, std::slice::from_ref(&msg)).await?;
+    if filter
+        .as_ref()
+        .is_some_and(|filter| filter.action != FilterAction::Warn)
+    {
+        return Ok(());
+    }
+
+    let note = db.user_lang(chat_id.0, None).await?.edited_note();
+    // album parts have their buttons in a separate message
+    let keyboard = match msg.media_group_id() {
+        Some(_) => InlineKeyboardMarkup::default(),
+        None => message_keyboard(&db, chat_id).await?,
+    };
+
+    let (caption, caption_entities) = with_note(
+        msg.caption().unwrap_or_default(),
+        msg.caption_entities().unwrap_or_default(),
+        note,
+        MAX_CAPTION_LENGTH,
+    );
+
+    let res = if let Some(text) = msg.text() {
+        let (text, entities) = with_note(
+            text,
+            msg.entities().unwrap_or_default(),
+            note,
+            MAX_TEXT_LENGTH,
+        );
+        bot.edit_message_text(chat_id, message_id, text)
+            .entities(entities)
+            .reply_markup(keyboard)
+            .await
+    } else if let Some(media) = input_media(&msg, caption.clone(), caption_entities.clone()) {
+        bot.edit_message_media(chat_id, message_id, media)
+            .reply_markup(keyboard)
+            .await
+    } else if msg.caption().is_some() || msg.voice().is_some() {
+        bot.edit_message_caption(chat_id, message_id)
+            .caption(caption)
+            .caption_entities(caption_entities)
+            .reply_markup(keyboard)
+            .await
+    } else {
+        // stickers, video notes, locations and the like can't be edited
+        return Ok(());
+    };
+    if let Err(e) = res {
+        warn!(
+            "can't edit copy of message {} in {}: {e}",
+            msg.id, msg.chat.id
+        );
+    } else if let Some(filter) = &filter {
+        filters::warn_recipient(&bot, &db, chat_id.0, message_id, filter).await?;
+    }
+    Ok(())
+}
+
+/// Appends the note to the text, cutting the text short if both don't fit into `limit`.
+/// The note goes last so that entity offsets stay valid, entities are clipped to the cut.
+fn with_note(
+    text: &str,
+    entities: &[MessageEntity],
+    note: &str,
+    limit: usize,
+) -> (String, Vec<MessageEntity>) {
+    if text.is_empty() {
+        return (note.to_owned(), Vec::new());
+    }
+    // Telegram counts lengths and offsets in UTF-16 code units
+    let utf16_len = |s: &str| s.encode_utf16().count();
+    let room = limit.saturating_sub(utf16_len(note) + 2);
+    let mut text = text.to_owned();
+    if utf16_len(&text) > room {
+        let mut len = 0;
+        let cut = text
+            .char_indices()
+            .find(|(_, c)| {
+                len += c.len_utf16();
+                len + 1 > room
+            })
+            .map_or(text.len(), |(i, _)| i);
+        text.truncate(cut);
+        text.push('…');
+    }
+
+    let text_len = utf16_len(&text);
+    let entities = entities
+        .iter()
+        .filter(|entity| entity.offset < text_len)
+        .map(|entity| MessageEntity {
+            length: entity.length.min(text_len - entity.offset),
+            ..entity.clone()
+        })
+        .collect();
+    (format!("{text}\n\n{note}"), entities)
+}
+
+/// New media for the copy, Telegram only allows replacing media of these types.
+fn input_media(msg: &Message, caption: String, entities: Vec<MessageEntity>) -> Option<InputMedia> {
+    let media = if let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) {
+        InputMedia::Photo(
+            InputMediaPhoto::new(InputFile::file_id(&photo.file.id))
+                .caption(caption)
+                .caption_entities(entities),
+        )
+    } else if let Some(video) = msg.video() {
+        InputMedia::Video(
+            InputMediaVideo::new(InputFile::file_id(&video.file.id))
+                .caption(caption)
+                .caption_entities(entities),
+        )
+    } else if let Some(animation) = msg.animation() {
+        InputMedia::Animation(
+            InputMediaAnimation::new(InputFile::file_id(&animation.file.id))
+                .caption(caption)
+                .caption_entities(entities),
+        )
+    } else if let Some(audio) = msg.audio() {
+        InputMedia::Audio(
+            InputMediaAudio::new(InputFile::file_id(&audio.file.id))
+                .caption(caption)
+                .caption_entities(entities),
+        )
+    } else if let Some(document) = msg.document() {
+        InputMedia::Document(
+            InputMediaDocument::new(InputFile::file_id(&document.file.id))
+                .caption(caption)
+                .caption_entities(entities),
+        )
+    } else {
+        return None;
+    };
+    Some(media)
+}
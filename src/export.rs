@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use entities::{held_messages, messages};
+use sea_orm::prelude::{DateTime, Json};
+use serde::Serialize;
+use teloxide::{prelude::*, types::InputFile};
+
+use crate::{db::Db, i18n::Lang, Bot};
+
+/// Everything the bot stores about a user. Other users are never named: there are no ids
+/// of senders, recipients or whoever invited the user, only the user's own side of messages.
+#[derive(Serialize)]
+struct Export {
+    user: UserData,
+    links: Vec<LinkData>,
+    filters: Vec<FilterData>,
+    /// When each of the blocked senders was blocked.
+    blocked_senders: Vec<DateTime>,
+    messages: Vec<MessageData>,
+    held_messages: Vec<HeldMessageData>,
+    /// Reports the user filed.
+    reports: Vec<ReportData>,
+}
+
+#[derive(Serialize)]
+struct UserData {
+    id: i64,
+    /// Whether the user came via someone else's link.
+    invited: bool,
+    first_activity: DateTime,
+    last_activity: DateTime,
+    language: Option<String>,
+    language_code: Option<String>,
+    answer_tip: bool,
+    paused: bool,
+    closed_note: Option<String>,
+    schedule: Option<Json>,
+    accepted_types: Option<Json>,
+    max_text_length: Option<i32>,
+    retention_days: Option<i32>,
+    banned_at: Option<DateTime>,
+    ban_expires_at: Option<DateTime>,
+    ban_reason: Option<String>,
+    admin_role: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct LinkData {
+    code: String,
+    label: Option<String>,
+    opens: i32,
+    received: u64,
+    created_at: DateTime,
+    disabled_at: Option<DateTime>,
+}
+
+#[derive(Serialize)]
+struct FilterData {
+    action: String,
+    kind: String,
+    pattern: Option<String>,
+    created_at: DateTime,
+}
+
+#[derive(Serialize)]
+struct MessageData {
+    /// `sent` or `received`.
+    direction: &'static str,
+    /// The id in the user's own chat with the bot.
+    message_id: i32,
+    conversation_id: i32,
+    timestamp: DateTime,
+    retracted_at: Option<DateTime>,
+}
+
+#[derive(Serialize)]
+struct HeldMessageData {
+    /// `sent` or `received`.
+    direction: &'static str,
+    /// Only sent messages have one, received ones aren't in the user's chat until released.
+    message_id: Option<i32>,
+    /// The recipient's filter that held the message, only for received ones.
+    reason: Option<String>,
+    timestamp: DateTime,
+}
+
+#[derive(Serialize)]
+struct ReportData {
+    /// The reported message in the user's chat with the bot.
+    message_id: i32,
+    reason: String,
+    status: String,
+    created_at: DateTime,
+    decided_at: Option<DateTime>,
+}
+
+impl HeldMessageData {
+    fn new(user_id: i64, held: held_messages::Model) -> Self {
+        let (direction, message_id, reason) = if held.sender_id == user_id {
+            ("sent", Some(held.sender_message_id), None)
+        } else {
+            ("received", None, Some(held.reason))
+        };
+        HeldMessageData {
+            direction,
+            message_id,
+            reason,
+            timestamp: held.timestamp,
+        }
+    }
+}
+
+impl MessageData {
+    fn new(user_id: i64, message: messages::Model) -> Self {
+        let (direction, message_id) = if message.sender_id == user_id {
+            ("sent", message.sender_message_id)
+        } else {
+            ("received", message.recipient_message_id)
+        };
+        MessageData {
+            direction,
+            message_id,
+            conversation_id: message.conversation_id,
+            timestamp: message.timestamp,
+            retracted_at: message.retracted_at,
+        }
+    }
+}
+
+async fn collect(db: &Db, user_id: i64) -> Result<Export> {
+    let user = db.get_user(user_id).await?.context("user not found")?;
+    let links = db.get_links(user_id, true).await?;
+    let filters = db.get_filters(user_id).await?;
+    let blocks = db.get_blocks(user_id).await?;
+    let messages = db.get_user_messages(user_id).await?;
+    let held = db.get_user_held(user_id).await?;
+    let reports = db.get_user_reports(user_id).await?;
+    let admin_role = db.admin_role(user_id).await?;
+
+    Ok(Export {
+        user: UserData {
+            id: user.id,
+            invited: user.invited_by.is_some(),
+            first_activity: user.first_activity,
+            last_activity: user.last_activity,
+            language: user.language,
+            language_code: user.language_code,
+            answer_tip: user.answer_tip,
+            paused: user.paused,
+            closed_note: user.closed_note,
+            schedule: user.schedule,
+            accepted_types: user.accepted_types,
+            max_text_length: user.max_text_length,
+            retention_days: user.retention_days,
+            banned_at: user.banned_at,
+            ban_expires_at: user.ban_expires_at,
+            ban_reason: user.ban_reason,
+            admin_role: admin_role.map(|role| role.as_str()),
+        },
+        links: links
+            .into_iter()
+            .map(|(link, received)| LinkData {
+                code: link.code,
+                label: link.label,
+                opens: link.opens,
+                received,
+                created_at: link.timestamp,
+                disabled_at: link.disabled_at,
+            })
+            .collect(),
+        filters: filters
+            .into_iter()
+            .map(|filter| FilterData {
+                action: filter.action,
+                kind: filter.kind,
+                pattern: filter.pattern,
+                created_at: filter.timestamp,
+            })
+            .collect(),
+        blocked_senders: blocks.into_iter().map(|block| block.timestamp).collect(),
+        messages: messages
+            .into_iter()
+            .map(|message| MessageData::new(user_id, message))
+            .collect(),
+        held_messages: held
+            .into_iter()
+            .map(|held| HeldMessageData::new(user_id, held))
+            .collect(),
+        reports: reports
+            .into_iter()
+            .map(|report| ReportData {
+                message_id: report.reporter_message_id,
+                reason: report.reason,
+                status: report.status,
+                created_at: report.timestamp,
+                decided_at: report.decided_at,
+            })
+            .collect(),
+    })
+}
+
+/// Messages as a table, the rest doesn't fit one.
+fn messages_csv(messages: &[MessageData]) -> String {
+    let mut csv = "direction,message_id,conversation_id,timestamp,retracted_at\n".to_owned();
+    for message in messages {
+        let retracted_at = message
+            .retracted_at
+            .map(|t| t.to_string())
+            .unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{},{},{retracted_at}\n",
+            message.direction, message.message_id, message.conversation_id, message.timestamp,
+        ));
+    }
+    csv
+}
+
+pub async fn handle_command_export(bot: Bot, db: Arc<Db>, msg: Message, lang: Lang) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let export = collect(&db, msg.chat.id.0).await?;
+    let json = serde_json::to_vec_pretty(&export)?;
+    let csv = messages_csv(&export.messages);
+
+    bot.send_document(
+        msg.chat.id,
+        InputFile::memory(json).file_name("export.json"),
+    )
+    .caption(lang.export_caption())
+    .await?;
+    bot.send_document(
+        msg.chat.id,
+        InputFile::memory(csv.into_bytes()).file_name("messages.csv"),
+    )
+    .await?;
+    Ok(())
+}
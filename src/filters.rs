@@ -0,0 +1,468 @@
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use entities::filters;
+use regex::{Regex, RegexBuilder};
+use teloxide::{
+    prelude::*,
+    types::{
+        InlineKeyboardButton, InlineKeyboardMarkup, MessageEntityKind, MessageId, ReplyParameters,
+    },
+};
+use tracing::*;
+
+use crate::{db::Db, forward_parts, i18n::Lang, Bot};
+
+const MAX_FILTERS: usize = 30;
+const MAX_PATTERN_LEN: usize = 200;
+/// Keeps compiled user regexes small, the default limit is far more than filters need.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Ordered from the mildest to the strictest, the strictest matching filter wins.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum FilterAction {
+    /// Delivers the message with a warning for the recipient.
+    Warn,
+    /// Keeps the message until the recipient reviews it with /held.
+    Hold,
+    /// Discards the message.
+    Drop,
+}
+
+impl FilterAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilterAction::Warn => "warn",
+            FilterAction::Hold => "hold",
+            FilterAction::Drop => "drop",
+        }
+    }
+}
+
+impl FromStr for FilterAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "warn" => Ok(FilterAction::Warn),
+            "hold" => Ok(FilterAction::Hold),
+            "drop" => Ok(FilterAction::Drop),
+            _ => bail!("unknown filter action: {s}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FilterKind {
+    /// A whole word or phrase, case-insensitive.
+    Word,
+    Regex,
+    /// Any link, as detected by Telegram.
+    Url,
+    /// Any @mention, as detected by Telegram.
+    Mention,
+}
+
+impl FilterKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilterKind::Word => "word",
+            FilterKind::Regex => "regex",
+            FilterKind::Url => "url",
+            FilterKind::Mention => "mention",
+        }
+    }
+}
+
+impl FromStr for FilterKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "word" => Ok(FilterKind::Word),
+            "regex" => Ok(FilterKind::Regex),
+            "url" => Ok(FilterKind::Url),
+            "mention" => Ok(FilterKind::Mention),
+            _ => bail!("unknown filter kind: {s}"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Filter {
+    pub action: FilterAction,
+    pub kind: FilterKind,
+    /// Only words and regexes have a pattern.
+    pub pattern: Option<String>,
+}
+
+impl Filter {
+    /// `None` for kinds matched by message entities instead of text.
+    fn regex(&self) -> Result<Option<Regex>> {
+        let pattern = match (self.kind, &self.pattern) {
+            (FilterKind::Word, Some(word)) => {
+                format!(r"(?:^|\W){}(?:\W|$)", regex::escape(word))
+            }
+            (FilterKind::Regex, Some(pattern)) => pattern.clone(),
+            (FilterKind::Url | FilterKind::Mention, _) => return Ok(None),
+            (kind, None) => bail!("{} filter without pattern", kind.as_str()),
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()?;
+        Ok(Some(regex))
+    }
+
+    /// Checks the text or caption of the message.
+    fn matches(&self, msg: &Message) -> Result<bool> {
+        let entities = msg
+            .entities()
+            .or(msg.caption_entities())
+            .unwrap_or_default();
+        let matches = match self.kind {
+            FilterKind::Url => entities.iter().any(|e| {
+                matches!(
+                    e.kind,
+                    MessageEntityKind::Url | MessageEntityKind::TextLink { .. }
+                )
+            }),
+            FilterKind::Mention => entities.iter().any(|e| {
+                matches!(
+                    e.kind,
+                    MessageEntityKind::Mention | MessageEntityKind::TextMention { .. }
+                )
+            }),
+            FilterKind::Word | FilterKind::Regex => {
+                let text = msg.text().or(msg.caption()).unwrap_or_default();
+                self.regex()?.is_some_and(|r| r.is_match(text))
+            }
+        };
+        Ok(matches)
+    }
+
+    /// Formats the filter the way `from_str` accepts it.
+    pub fn describe(&self) -> String {
+        match &self.pattern {
+            Some(pattern) => format!("{} {} {pattern}", self.action.as_str(), self.kind.as_str()),
+            None => format!("{} {}", self.action.as_str(), self.kind.as_str()),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    /// Parses `<action> <kind> [pattern]`, e.g. `hold regex \+?\d{10,}`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (action, rest) = s
+            .trim()
+            .split_once(char::is_whitespace)
+            .context("expected action and filter kind")?;
+        let rest = rest.trim_start();
+        let (kind, pattern) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let pattern = pattern.trim();
+
+        let filter = Filter {
+            action: action.parse()?,
+            kind: kind.parse()?,
+            pattern: (!pattern.is_empty()).then(|| pattern.to_owned()),
+        };
+        match (filter.kind, &filter.pattern) {
+            (FilterKind::Url | FilterKind::Mention, Some(_)) => {
+                bail!("{} filters don't take a pattern", filter.kind.as_str())
+            }
+            (_, Some(pattern)) if pattern.chars().count() > MAX_PATTERN_LEN => {
+                bail!("pattern is too long")
+            }
+            _ => {}
+        }
+        filter.regex()?;
+        Ok(filter)
+    }
+}
+
+impl TryFrom<&filters::Model> for Filter {
+    type Error = anyhow::Error;
+
+    fn try_from(model: &filters::Model) -> Result<Self> {
+        Ok(Filter {
+            action: model.action.parse()?,
+            kind: model.kind.parse()?,
+            pattern: model.pattern.clone(),
+        })
+    }
+}
+
+/// The strictest of the recipient's filters matching the message or any part of the album.
+pub async fn check(db: &Db, recipient_id: i64, parts: &[Message]) -> Result<Option<Filter>> {
+    let mut matched: Option<Filter> = None;
+    for model in db.get_filters(recipient_id).await? {
+        let filter = Filter::try_from(&model)?;
+        let matches = parts
+            .iter()
+            .map(|part| filter.matches(part))
+            .find(|m| !matches!(m, Ok(false)))
+            .unwrap_or(Ok(false));
+        match matches {
+            Ok(true) if matched.as_ref().is_none_or(|m| filter.action > m.action) => {
+                matched = Some(filter)
+            }
+            Ok(_) => {}
+            Err(e) => warn!("broken filter {}: {e}", model.id),
+        }
+    }
+    Ok(matched)
+}
+
+/// Keeps the message, or every part of the album, for the recipient to review,
+/// `reply_to` is the message in the recipient's chat it answers.
+pub async fn hold(
+    bot: &Bot,
+    db: &Db,
+    parts: &[Message],
+    recipient_id: i64,
+    reply_to: Option<i32>,
+    link_id: Option<i32>,
+    filter: &Filter,
+) -> Result<()> {
+    for part in parts {
+        db.hold_message(
+            part.chat.id.0,
+            part.id.0,
+            recipient_id,
+            reply_to,
+            link_id,
+            &filter.describe(),
+            part.media_group_id(),
+        )
+        .await?;
+    }
+    let lang = db.user_lang(recipient_id, None).await?;
+    if let Err(e) = bot
+        .send_message(ChatId(recipient_id), lang.message_held())
+        .disable_notification(true)
+        .await
+    {
+        warn!("can't notify {recipient_id} about a held message: {e}");
+    }
+    Ok(())
+}
+
+/// Remembers dropped messages, so that unsending one looks the same as unsending a delivered one.
+pub async fn discard(db: &Db, parts: &[Message], recipient_id: i64) -> Result<()> {
+    let Some(msg) = parts.first() else {
+        return Ok(());
+    };
+    let message_ids = parts.iter().map(|part| part.id.0).collect::<Vec<_>>();
+    db.drop_messages(msg.chat.id.0, recipient_id, &message_ids)
+        .await
+}
+
+/// Points the recipient to the filter a delivered message matched.
+pub async fn warn_recipient(
+    bot: &Bot,
+    db: &Db,
+    recipient_id: i64,
+    message_id: MessageId,
+    filter: &Filter,
+) -> Result<()> {
+    let lang = db.user_lang(recipient_id, None).await?;
+    if let Err(e) = bot
+        .send_message(
+            ChatId(recipient_id),
+            lang.filter_warning(&filter.describe()),
+        )
+        .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply())
+        .await
+    {
+        warn!("can't warn {recipient_id} about a filtered message: {e}");
+    }
+    Ok(())
+}
+
+pub async fn filters_message(
+    db: &Db,
+    user_id: i64,
+    lang: Lang,
+) -> Result<(String, InlineKeyboardMarkup)> {
+    let filters = db.get_filters(user_id).await?;
+
+    let mut lines = Vec::new();
+    let mut buttons = Vec::new();
+    if filters.is_empty() {
+        lines.push(lang.no_filters().to_owned());
+    } else {
+        lines.push(lang.filters_header().to_owned());
+        for model in &filters {
+            let filter = Filter::try_from(model)?.describe();
+            buttons.push([InlineKeyboardButton::callback(
+                lang.remove_filter_button(&filter),
+                format!("remove_filter:{}", model.id),
+            )]);
+            lines.push(filter);
+        }
+    }
+    lines.push(String::new());
+    lines.push(lang.filter_usage().to_owned());
+
+    let held = db.count_held(user_id).await?;
+    if held > 0 {
+        lines.push(String::new());
+        lines.push(lang.held_count(held));
+    }
+    Ok((lines.join("\n"), InlineKeyboardMarkup::new(buttons)))
+}
+
+pub async fn handle_command_filters(bot: Bot, db: Arc<Db>, msg: Message, lang: Lang) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let (text, keyboard) = filters_message(&db, msg.chat.id.0, lang).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+pub async fn handle_command_filter(
+    bot: Bot,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+    args: String,
+) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let Ok(filter) = args.parse::<Filter>() else {
+        bot.send_message(msg.chat.id, lang.filter_usage()).await?;
+        return Ok(());
+    };
+    if db.get_filters(msg.chat.id.0).await?.len() >= MAX_FILTERS {
+        bot.send_message(msg.chat.id, lang.too_many_filters(MAX_FILTERS))
+            .await?;
+        return Ok(());
+    }
+
+    db.add_filter(msg.chat.id.0, &filter).await?;
+    bot.send_message(msg.chat.id, lang.filter_added(&filter.describe()))
+        .await?;
+    Ok(())
+}
+
+pub async fn handle_command_held(bot: Bot, db: Arc<Db>, msg: Message, lang: Lang) -> Result<()> {
+    show_next_held(&bot, &db, msg.chat.id, lang).await
+}
+
+/// Shows the oldest held message followed by its review buttons, replying to the message copy.
+async fn show_next_held(bot: &Bot, db: &Db, chat_id: ChatId, lang: Lang) -> Result<()> {
+    loop {
+        let Some((parts, count)) = db.next_held(chat_id.0).await? else {
+            bot.send_message(chat_id, lang.no_held_messages()).await?;
+            return Ok(());
+        };
+        let held = parts.first().context("no held message")?;
+
+        let copies = match bot
+            .copy_messages(
+                chat_id,
+                ChatId(held.sender_id),
+                parts.iter().map(|part| MessageId(part.sender_message_id)),
+            )
+            .await
+        {
+            Ok(copies) if !copies.is_empty() => copies,
+            Ok(_) => {
+                warn!("held message {} is gone", held.id);
+                db.take_held(chat_id.0, held.id).await?;
+                continue;
+            }
+            Err(e) => {
+                // the sender deleted the message or blocked the bot
+                warn!("held message {} is gone: {e}", held.id);
+                db.take_held(chat_id.0, held.id).await?;
+                continue;
+            }
+        };
+        let previews = parts
+            .iter()
+            .zip(&copies)
+            .map(|(part, copy)| (part.id, copy.0))
+            .collect::<Vec<_>>();
+        db.set_held_previews(&previews).await?;
+        bot.send_message(chat_id, lang.held_message_info(&held.reason, count))
+            .reply_parameters(ReplyParameters::new(copies[0]).allow_sending_without_reply())
+            .reply_markup(InlineKeyboardMarkup::new([[
+                InlineKeyboardButton::callback(
+                    lang.deliver_held_button(),
+                    format!("held_deliver:{}", held.id),
+                ),
+                InlineKeyboardButton::callback(
+                    lang.delete_held_button(),
+                    format!("held_delete:{}", held.id),
+                ),
+            ]]))
+            .await?;
+        return Ok(());
+    }
+}
+
+pub async fn handle_held_action(
+    bot: &Bot,
+    db: &Db,
+    q: &CallbackQuery,
+    chat_id: ChatId,
+    held_id: i32,
+    deliver: bool,
+    lang: Lang,
+) -> Result<()> {
+    let parts = db.take_held(chat_id.0, held_id).await?;
+    if let Some(prompt) = q.message.as_ref().and_then(|m| m.regular_message()) {
+        // only the copies shown for review and the prompt, the chat may have other
+        // messages in between
+        let preview = parts
+            .iter()
+            .filter_map(|part| part.preview_message_id.map(MessageId))
+            .chain([prompt.id]);
+        if let Err(e) = bot.delete_messages(chat_id, preview).await {
+            warn!("can't delete held message preview: {e}");
+        }
+    }
+
+    if let Some(held) = parts.first()
+        && deliver
+    {
+        let message_ids = parts
+            .iter()
+            .map(|part| MessageId(part.sender_message_id))
+            .collect::<Vec<_>>();
+        match forward_parts(
+            bot,
+            db,
+            ChatId(held.sender_id),
+            &message_ids,
+            chat_id,
+            held.reply_to.map(MessageId),
+        )
+        .await
+        {
+            Ok(message_ids) => {
+                let replied = match held.reply_to {
+                    Some(reply_to) => db.find_message(chat_id.0, reply_to).await?,
+                    None => None,
+                };
+                db.save_messages(
+                    held.sender_id,
+                    chat_id.0,
+                    &message_ids,
+                    held.link_id,
+                    held.media_group_id.as_deref(),
+                    replied.as_ref(),
+                )
+                .await?;
+            }
+            Err(e) => {
+                bot.send_message(chat_id, lang.held_delivery_failed(&e.to_string()))
+                    .await?;
+            }
+        }
+    }
+    show_next_held(bot, db, chat_id, lang).await
+}
@@ -0,0 +1,1403 @@
+use teloxide::types::BotCommand;
+
+use crate::{
+    inbox::MessageType,
+    moderation::{ReportReason, ReportStatus},
+    rate_limit::LimitScope,
+    settings::Setting,
+};
+
+/// English is the fallback for users whose language is unknown or not supported.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Lang {
+    Ru,
+    #[default]
+    En,
+    Uk,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 3] = [Lang::Ru, Lang::En, Lang::Uk];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::Ru => "ru",
+            Lang::En => "en",
+            Lang::Uk => "uk",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.split(['-', '_']).next()?;
+        Self::ALL
+            .into_iter()
+            .find(|l| l.code().eq_ignore_ascii_case(code))
+    }
+
+    /// Picks a language from Telegram's `language_code`, the default one for unknown ones.
+    pub fn detect(language_code: Option<&str>) -> Self {
+        language_code.and_then(Self::from_code).unwrap_or_default()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lang::Ru => "🇷🇺 Русский",
+            Lang::En => "🇬🇧 English",
+            Lang::Uk => "🇺🇦 Українська",
+        }
+    }
+
+    /// Localized copy of `commands`, descriptions without a translation are kept as is.
+    pub fn bot_commands(self, commands: Vec<BotCommand>) -> Vec<BotCommand> {
+        commands
+            .into_iter()
+            .map(|c| match self.command_description(&c.command) {
+                Some(description) => BotCommand::new(c.command, description),
+                None => c,
+            })
+            .collect()
+    }
+
+    fn command_description(self, command: &str) -> Option<&'static str> {
+        let description = match (command.trim_start_matches('/'), self) {
+            ("start", Lang::Ru) => "Получить свою ссылку",
+            ("start", Lang::En) => "Get your link",
+            ("start", Lang::Uk) => "Отримати своє посилання",
+            ("language", Lang::Ru) => "Сменить язык",
+            ("language", Lang::En) => "Change language",
+            ("language", Lang::Uk) => "Змінити мову",
+            ("blocked", Lang::Ru) => "Заблокированные отправители",
+            ("blocked", Lang::En) => "Blocked senders",
+            ("blocked", Lang::Uk) => "Заблоковані відправники",
+            ("newlink", Lang::Ru) => "Сменить ссылку",
+            ("newlink", Lang::En) => "Get a new link",
+            ("newlink", Lang::Uk) => "Змінити посилання",
+            ("setlink", Lang::Ru) => "Задать свой адрес ссылки",
+            ("setlink", Lang::En) => "Choose a custom link",
+            ("setlink", Lang::Uk) => "Задати власну адресу посилання",
+            ("pause", Lang::Ru) => "Перестать принимать сообщения",
+            ("pause", Lang::En) => "Stop receiving messages",
+            ("pause", Lang::Uk) => "Перестати приймати повідомлення",
+            ("resume", Lang::Ru) => "Снова принимать сообщения",
+            ("resume", Lang::En) => "Receive messages again",
+            ("resume", Lang::Uk) => "Знову приймати повідомлення",
+            ("schedule", Lang::Ru) => "Часы приёма сообщений",
+            ("schedule", Lang::En) => "Hours for receiving messages",
+            ("schedule", Lang::Uk) => "Години прийому повідомлень",
+            ("accept", Lang::Ru) => "Какие сообщения принимать",
+            ("accept", Lang::En) => "Which messages to accept",
+            ("accept", Lang::Uk) => "Які повідомлення приймати",
+            ("maxlength", Lang::Ru) => "Максимальная длина текста",
+            ("maxlength", Lang::En) => "Maximum text length",
+            ("maxlength", Lang::Uk) => "Максимальна довжина тексту",
+            ("filters", Lang::Ru) => "Фильтры сообщений",
+            ("filters", Lang::En) => "Message filters",
+            ("filters", Lang::Uk) => "Фільтри повідомлень",
+            ("filter", Lang::Ru) => "Добавить фильтр",
+            ("filter", Lang::En) => "Add a filter",
+            ("filter", Lang::Uk) => "Додати фільтр",
+            ("held", Lang::Ru) => "Задержанные фильтрами сообщения",
+            ("held", Lang::En) => "Messages held by filters",
+            ("held", Lang::Uk) => "Затримані фільтрами повідомлення",
+            ("settings", Lang::Ru) => "Настройки",
+            ("settings", Lang::En) => "Settings",
+            ("settings", Lang::Uk) => "Налаштування",
+            ("unsend", Lang::Ru) => "Удалить своё сообщение у получателя (ответом на него)",
+            ("unsend", Lang::En) => "Delete your message for the recipient (as a reply to it)",
+            ("unsend", Lang::Uk) => "Видалити своє повідомлення в одержувача (відповіддю на нього)",
+            ("inbox", Lang::Ru) => "Полученные сообщения",
+            ("inbox", Lang::En) => "Received messages",
+            ("inbox", Lang::Uk) => "Отримані повідомлення",
+            ("export", Lang::Ru) => "Выгрузить свои данные",
+            ("export", Lang::En) => "Export your data",
+            ("export", Lang::Uk) => "Вивантажити свої дані",
+            ("delete_me", Lang::Ru) => "Удалить свой аккаунт и все данные",
+            ("delete_me", Lang::En) => "Delete your account and all data",
+            ("delete_me", Lang::Uk) => "Видалити свій акаунт і всі дані",
+            ("retention", Lang::Ru) => "Сколько хранить историю сообщений",
+            ("retention", Lang::En) => "How long to keep message history",
+            ("retention", Lang::Uk) => "Скільки зберігати історію повідомлень",
+            ("links", Lang::Ru) => "Мои ссылки и статистика",
+            ("links", Lang::En) => "My links and statistics",
+            ("links", Lang::Uk) => "Мої посилання та статистика",
+            ("addlink", Lang::Ru) => "Создать ссылку с подписью",
+            ("addlink", Lang::En) => "Create a labeled link",
+            ("addlink", Lang::Uk) => "Створити посилання з підписом",
+            _ => return None,
+        };
+        Some(description)
+    }
+
+    pub fn bot_description(self) -> &'static str {
+        match self {
+            Lang::Ru => "Бот для анонимных вопросов и сообщений. \
+                Получите свою ссылку, опубликуйте её и отвечайте на сообщения, не раскрывая собеседников.",
+            Lang::En => "A bot for anonymous questions and messages. \
+                Get your link, share it and reply to messages without revealing anyone's identity.",
+            Lang::Uk => "Бот для анонімних запитань і повідомлень. \
+                Отримайте своє посилання, опублікуйте його та відповідайте на повідомлення, не розкриваючи співрозмовників.",
+        }
+    }
+
+    pub fn bot_short_description(self) -> &'static str {
+        match self {
+            Lang::Ru => "Анонимные вопросы и сообщения",
+            Lang::En => "Anonymous questions and messages",
+            Lang::Uk => "Анонімні запитання та повідомлення",
+        }
+    }
+
+    fn own_link(self, link: &str) -> String {
+        match self {
+            Lang::Ru => format!(
+                "А вот, кстати, ваша собственная ссылка для получения анонимных вопросов и сообщений: {link}"
+            ),
+            Lang::En => format!(
+                "By the way, here is your own link for receiving anonymous questions and messages: {link}"
+            ),
+            Lang::Uk => format!(
+                "До речі, ось ваше власне посилання для отримання анонімних запитань і повідомлень: {link}"
+            ),
+        }
+    }
+
+    pub fn welcome(self, link: &str) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Добро пожаловать! \
+                Чтобы начать получать анонимные вопросы, опубликуйте свою личную ссылку в канале: {link}. \
+                Возможна отправка любых сообщений: текстовых, фото, стикеров и прочих."
+            ),
+            Lang::En => format!(
+                "Welcome! \
+                To start receiving anonymous questions, post your personal link in your channel: {link}. \
+                Any kind of message can be sent: text, photos, stickers and more."
+            ),
+            Lang::Uk => format!(
+                "Ласкаво просимо! \
+                Щоб почати отримувати анонімні запитання, опублікуйте своє особисте посилання в каналі: {link}. \
+                Можна надсилати будь-які повідомлення: текстові, фото, стікери та інші."
+            ),
+        }
+    }
+
+    pub fn send_your_message(self) -> &'static str {
+        match self {
+            Lang::Ru => {
+                "Отправьте ваше анонимное сообщение (что угодно - текст, фото, стикер, ...):"
+            }
+            Lang::En => "Send your anonymous message (anything - text, photo, sticker, ...):",
+            Lang::Uk => {
+                "Надішліть ваше анонімне повідомлення (що завгодно - текст, фото, стікер, ...):"
+            }
+        }
+    }
+
+    pub fn cancel_button(self) -> &'static str {
+        match self {
+            Lang::Ru => "Отмена",
+            Lang::En => "Cancel",
+            Lang::Uk => "Скасувати",
+        }
+    }
+
+    pub fn reply_button(self) -> &'static str {
+        match self {
+            Lang::Ru => "Ответить",
+            Lang::En => "Reply",
+            Lang::Uk => "Відповісти",
+        }
+    }
+
+    pub fn link_invalid(self, own_link: &str) -> String {
+        let text = match self {
+            Lang::Ru => "Ссылка недействительна! Попросите автора создать новую ссылку.",
+            Lang::En => "This link is invalid! Ask its author to create a new one.",
+            Lang::Uk => "Посилання недійсне! Попросіть автора створити нове посилання.",
+        };
+        format!("{text} {}", self.own_link(own_link))
+    }
+
+    pub fn unexpected_message(self, own_link: &str) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Кажется, вы отправили сообщение, но мы его не ждали... Может быть, \
+                вы хотели отправить кому-то сообщение или ответить на полученное? В таком случае перейдите по ссылке друга или свайпните \
+                полученное/отправленное сообщение влево (ответьте). А если вы хотите начать получать сообщения сами, то держите ссылку: {own_link}"
+            ),
+            Lang::En => format!(
+                "Looks like you sent a message we weren't expecting... Perhaps \
+                you wanted to message someone or reply to a message you received? In that case open your friend's link or swipe \
+                the received/sent message to the left (reply to it). And if you want to start receiving messages yourself, here is your link: {own_link}"
+            ),
+            Lang::Uk => format!(
+                "Здається, ви надіслали повідомлення, але ми його не чекали... Можливо, \
+                ви хотіли надіслати комусь повідомлення або відповісти на отримане? У такому разі перейдіть за посиланням друга або свайпніть \
+                отримане/надіслане повідомлення вліво (дайте відповідь). А якщо ви хочете самі отримувати повідомлення, ось ваше посилання: {own_link}"
+            ),
+        }
+    }
+
+    pub fn reply_failed(self, error: &str) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Не удалось ответить на сообщение: {error}. Возможно, получатель заблокировал бота."
+            ),
+            Lang::En => format!(
+                "Failed to reply to the message: {error}. The recipient may have blocked the bot."
+            ),
+            Lang::Uk => format!(
+                "Не вдалося відповісти на повідомлення: {error}. Можливо, одержувач заблокував бота."
+            ),
+        }
+    }
+
+    pub fn reply_not_found(self) -> &'static str {
+        match self {
+            Lang::Ru => {
+                "Отвечать (свайпать слево) можно только на полученные и отправленные сообщения! \
+                Если это одно из них, этот разговор больше недоступен."
+            }
+            Lang::En => {
+                "You can only reply (swipe left) to received and sent messages! \
+                If this is one of them, the conversation is no longer available."
+            }
+            Lang::Uk => {
+                "Відповідати (свайпати вліво) можна лише на отримані та надіслані повідомлення! \
+                Якщо це одне з них, ця розмова більше недоступна."
+            }
+        }
+    }
+
+    pub fn replied_while_waiting(self) -> &'static str {
+        match self {
+            Lang::Ru => "Вы ответили на сообщение, пока мы ждали отправку вопроса.",
+            Lang::En => "You replied to a message while we were waiting for your question.",
+            Lang::Uk => "Ви відповіли на повідомлення, поки ми чекали на ваше запитання.",
+        }
+    }
+
+    pub fn message_sent(self, own_link: &str) -> String {
+        let text = match self {
+            Lang::Ru => "Ваше сообщение отправлено!",
+            Lang::En => "Your message has been sent!",
+            Lang::Uk => "Ваше повідомлення надіслано!",
+        };
+        format!("{text} {}", self.own_link(own_link))
+    }
+
+    pub fn unsend_button(self) -> &'static str {
+        match self {
+            Lang::Ru => "Удалить у получателя",
+            Lang::En => "Delete for recipient",
+            Lang::Uk => "Видалити в одержувача",
+        }
+    }
+
+    pub fn message_unsent(self) -> &'static str {
+        match self {
+            Lang::Ru => "Сообщение удалено у получателя.",
+            Lang::En => "The message was deleted for the recipient.",
+            Lang::Uk => "Повідомлення видалено в одержувача.",
+        }
+    }
+
+    pub fn message_already_unsent(self) -> &'static str {
+        match self {
+            Lang::Ru => "Это сообщение уже удалено.",
+            Lang::En => "This message has already been deleted.",
+            Lang::Uk => "Це повідомлення вже видалено.",
+        }
+    }
+
+    pub fn unsend_too_late(self, hours: i64) -> String {
+        match self {
+            Lang::Ru => {
+                format!("Сообщение можно удалить только в течение {hours} часов после отправки.")
+            }
+            Lang::En => {
+                format!("A message can only be deleted within {hours} hours after sending.")
+            }
+            Lang::Uk => {
+                format!("Повідомлення можна видалити лише протягом {hours} годин після надсилання.")
+            }
+        }
+    }
+
+    pub fn unsend_not_found(self) -> &'static str {
+        match self {
+            Lang::Ru => "Это сообщение не было отправлено через бота или уже не хранится.",
+            Lang::En => "This message wasn't sent through the bot or is no longer stored.",
+            Lang::Uk => "Це повідомлення не було надіслано через бота або вже не зберігається.",
+        }
+    }
+
+    pub fn unsend_usage(self) -> &'static str {
+        match self {
+            Lang::Ru => "Отправьте /unsend в ответ на своё сообщение, которое нужно удалить у получателя.",
+            Lang::En => "Send /unsend as a reply to your message that should be deleted for the recipient.",
+            Lang::Uk => "Надішліть /unsend у відповідь на своє повідомлення, яке треба видалити в одержувача.",
+        }
+    }
+
+    pub fn no_received_messages(self) -> &'static str {
+        match self {
+            Lang::Ru => "Вы пока не получили ни одного сообщения.",
+            Lang::En => "You haven't received any messages yet.",
+            Lang::Uk => "Ви ще не отримали жодного повідомлення.",
+        }
+    }
+
+    pub fn received_messages(self, count: u64, page: u64, pages: u64) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Полученных сообщений: {count} (страница {page} из {pages}). \
+                ⏳ отмечены сообщения без ответа. Нажмите на сообщение, чтобы найти его в чате."
+            ),
+            Lang::En => format!(
+                "Received messages: {count} (page {page} of {pages}). \
+                ⏳ marks unanswered ones. Press a message to find it in the chat."
+            ),
+            Lang::Uk => format!(
+                "Отриманих повідомлень: {count} (сторінка {page} з {pages}). \
+                ⏳ позначено повідомлення без відповіді. Натисніть на повідомлення, щоб знайти його в чаті."
+            ),
+        }
+    }
+
+    pub fn received_button(
+        self,
+        number: usize,
+        date: &str,
+        label: Option<&str>,
+        answered: bool,
+    ) -> String {
+        let mark = if answered { "✅" } else { "⏳" };
+        match label {
+            Some(label) => format!("{mark} #{number} · {date} · {label}"),
+            None => format!("{mark} #{number} · {date}"),
+        }
+    }
+
+    pub fn received_message_here(self) -> &'static str {
+        match self {
+            Lang::Ru => "⬆️ Это сообщение",
+            Lang::En => "⬆️ Here it is",
+            Lang::Uk => "⬆️ Ось це повідомлення",
+        }
+    }
+
+    pub fn received_message_gone(self) -> &'static str {
+        match self {
+            Lang::Ru => "Этого сообщения больше нет в чате.",
+            Lang::En => "This message is no longer in the chat.",
+            Lang::Uk => "Цього повідомлення більше немає в чаті.",
+        }
+    }
+
+    pub fn export_caption(self) -> &'static str {
+        match self {
+            Lang::Ru => "Всё, что бот хранит о вас. Данные других пользователей в выгрузку не входят, \
+                поэтому отправители и получатели сообщений не указаны. Текст сообщений бот не хранит.",
+            Lang::En => "Everything the bot stores about you. Other users' data isn't included, \
+                so senders and recipients of messages aren't named. The bot doesn't store message texts.",
+            Lang::Uk => "Усе, що бот зберігає про вас. Дані інших користувачів у вивантаження не входять, \
+                тому відправників і одержувачів повідомлень не вказано. Текст повідомлень бот не зберігає.",
+        }
+    }
+
+    pub fn delete_me_confirmation(self) -> &'static str {
+        match self {
+            Lang::Ru => {
+                "Удалить ваш аккаунт? Ваши ссылки перестанут работать, а сообщения, \
+                настройки, фильтры и блокировки будут стёрты. На полученные и отправленные \
+                сообщения больше нельзя будет ответить. Это нельзя отменить."
+            }
+            Lang::En => {
+                "Delete your account? Your links will stop working, and your messages, \
+                settings, filters and blocks will be erased. Nobody will be able to reply to \
+                messages you received or sent anymore. This can't be undone."
+            }
+            Lang::Uk => {
+                "Видалити ваш акаунт? Ваші посилання перестануть працювати, а повідомлення, \
+                налаштування, фільтри та блокування буде стерто. На отримані й надіслані \
+                повідомлення більше не можна буде відповісти. Це не можна скасувати."
+            }
+        }
+    }
+
+    pub fn delete_me_button(self) -> &'static str {
+        match self {
+            Lang::Ru => "Удалить всё",
+            Lang::En => "Delete everything",
+            Lang::Uk => "Видалити все",
+        }
+    }
+
+    pub fn account_deleted(self) -> &'static str {
+        match self {
+            Lang::Ru => {
+                "Ваш аккаунт и все данные удалены. Если вы снова напишете боту, \
+                для вас будет создан новый аккаунт."
+            }
+            Lang::En => {
+                "Your account and all data have been deleted. If you message the bot \
+                again, a new account will be created for you."
+            }
+            Lang::Uk => {
+                "Ваш акаунт і всі дані видалено. Якщо ви знову напишете боту, \
+                для вас буде створено новий акаунт."
+            }
+        }
+    }
+
+    pub fn nothing_to_delete(self) -> &'static str {
+        match self {
+            Lang::Ru => "Бот ничего о вас не хранит.",
+            Lang::En => "The bot doesn't store anything about you.",
+            Lang::Uk => "Бот нічого про вас не зберігає.",
+        }
+    }
+
+    pub fn send_failed(self, error: &str, own_link: &str) -> String {
+        let text = match self {
+            Lang::Ru => format!(
+                "Не удалось отправить сообщение: {error}. Возможно, получатель заблокировал бота."
+            ),
+            Lang::En => format!(
+                "Failed to send the message: {error}. The recipient may have blocked the bot."
+            ),
+            Lang::Uk => format!(
+                "Не вдалося надіслати повідомлення: {error}. Можливо, одержувач заблокував бота."
+            ),
+        };
+        format!("{text} {}", self.own_link(own_link))
+    }
+
+    pub fn send_cancelled(self, own_link: &str) -> String {
+        let text = match self {
+            Lang::Ru => "Отправка сообщения отменена!",
+            Lang::En => "Sending cancelled!",
+            Lang::Uk => "Надсилання повідомлення скасовано!",
+        };
+        format!("{text} {}", self.own_link(own_link))
+    }
+
+    pub fn reply_tip(self) -> &'static str {
+        match self {
+            Lang::Ru => "Для ответа используйте встроенную в Telegram функцию ответа на сообщение (свайпните влево)",
+            Lang::En => "To reply, use Telegram's built-in reply feature (swipe the message to the left)",
+            Lang::Uk => "Щоб відповісти, скористайтеся вбудованою в Telegram функцією відповіді на повідомлення (свайпніть вліво)",
+        }
+    }
+
+    pub fn reply_tip_disabled(self) -> String {
+        let text = match self {
+            Lang::Ru => "Эта подсказка больше не будет отображаться, вернуть её можно в /settings.",
+            Lang::En => "This tip will not be shown again, you can bring it back in /settings.",
+            Lang::Uk => "Ця підказка більше не відображатиметься, повернути її можна в /settings.",
+        };
+        format!("{}.\n\n{text}", self.reply_tip())
+    }
+
+    /// Sent right after an album to carry the buttons albums can't have.
+    pub fn album_buttons(self) -> &'static str {
+        match self {
+            Lang::Ru => "⬆️ Альбом выше",
+            Lang::En => "⬆️ Album above",
+            Lang::Uk => "⬆️ Альбом вище",
+        }
+    }
+
+    pub fn edited_note(self) -> &'static str {
+        match self {
+            Lang::Ru => "✏️ изменено",
+            Lang::En => "✏️ edited",
+            Lang::Uk => "✏️ змінено",
+        }
+    }
+
+    pub fn choose_language(self) -> &'static str {
+        match self {
+            Lang::Ru => "Выберите язык:",
+            Lang::En => "Choose your language:",
+            Lang::Uk => "Оберіть мову:",
+        }
+    }
+
+    pub fn language_changed(self) -> &'static str {
+        match self {
+            Lang::Ru => "Язык изменён на русский.",
+            Lang::En => "Language changed to English.",
+            Lang::Uk => "Мову змінено на українську.",
+        }
+    }
+
+    pub fn block_button(self) -> &'static str {
+        match self {
+            Lang::Ru => "🚫 Заблокировать",
+            Lang::En => "🚫 Block",
+            Lang::Uk => "🚫 Заблокувати",
+        }
+    }
+
+    pub fn sender_blocked(self) -> &'static str {
+        match self {
+            Lang::Ru => "Отправитель заблокирован, его сообщения больше не будут доставляться. Управлять блокировками: /blocked",
+            Lang::En => "The sender is blocked, their messages will no longer be delivered. Manage blocks: /blocked",
+            Lang::Uk => "Відправника заблоковано, його повідомлення більше не доставлятимуться. Керувати блокуваннями: /blocked",
+        }
+    }
+
+    pub fn message_not_delivered(self, own_link: &str) -> String {
+        let text = match self {
+            Lang::Ru => "Не удалось доставить сообщение.",
+            Lang::En => "The message could not be delivered.",
+            Lang::Uk => "Не вдалося доставити повідомлення.",
+        };
+        format!("{text} {}", self.own_link(own_link))
+    }
+
+    pub fn reply_not_delivered(self) -> &'static str {
+        match self {
+            Lang::Ru => "Не удалось доставить ответ.",
+            Lang::En => "The reply could not be delivered.",
+            Lang::Uk => "Не вдалося доставити відповідь.",
+        }
+    }
+
+    pub fn blocked_senders(self, count: usize) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Заблокированных отправителей: {count}. Нажмите на кнопку, чтобы разблокировать."
+            ),
+            Lang::En => format!("Blocked senders: {count}. Press a button to unblock."),
+            Lang::Uk => {
+                format!("Заблокованих відправників: {count}. Натисніть кнопку, щоб розблокувати.")
+            }
+        }
+    }
+
+    pub fn no_blocked_senders(self) -> &'static str {
+        match self {
+            Lang::Ru => "Вы никого не заблокировали.",
+            Lang::En => "You haven't blocked anyone.",
+            Lang::Uk => "Ви нікого не заблокували.",
+        }
+    }
+
+    pub fn unblock_button(self, number: usize, since: &str) -> String {
+        match self {
+            Lang::Ru => format!("Разблокировать #{number} (с {since})"),
+            Lang::En => format!("Unblock #{number} (since {since})"),
+            Lang::Uk => format!("Розблокувати #{number} (з {since})"),
+        }
+    }
+
+    pub fn new_link(self, link: &str, grace_hours: u32) -> String {
+        let old_link = match (self, grace_hours) {
+            (Lang::Ru, 0) => "Старая ссылка больше не работает.".to_owned(),
+            (Lang::Ru, hours) => format!("Старая ссылка будет работать ещё {hours} ч."),
+            (Lang::En, 0) => "The old link no longer works.".to_owned(),
+            (Lang::En, hours) => format!("The old link will keep working for {hours} h."),
+            (Lang::Uk, 0) => "Старе посилання більше не працює.".to_owned(),
+            (Lang::Uk, hours) => format!("Старе посилання працюватиме ще {hours} год."),
+        };
+        match self {
+            Lang::Ru => format!(
+                "Ваша новая ссылка: {link}. {old_link} Отвечать на уже полученные сообщения можно как раньше."
+            ),
+            Lang::En => format!(
+                "Your new link: {link}. {old_link} You can still reply to messages you have already received."
+            ),
+            Lang::Uk => format!(
+                "Ваше нове посилання: {link}. {old_link} Відповідати на вже отримані повідомлення можна як раніше."
+            ),
+        }
+    }
+
+    pub fn new_link_usage(self, max_hours: u32) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Использование: /newlink [часы], где часы - сколько ещё будет работать старая ссылка (не больше {max_hours})."
+            ),
+            Lang::En => format!(
+                "Usage: /newlink [hours], where hours is how long the old link keeps working (at most {max_hours})."
+            ),
+            Lang::Uk => format!(
+                "Використання: /newlink [години], де години - скільки ще працюватиме старе посилання (не більше {max_hours})."
+            ),
+        }
+    }
+
+    pub fn set_link_usage(self, min_len: usize, max_len: usize, max_hours: u32) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Использование: /setlink <адрес> [часы]. Адрес - от {min_len} до {max_len} латинских букв, цифр, _ или -, \
+                начинается с буквы. Часы - сколько ещё будет работать старая ссылка (не больше {max_hours})."
+            ),
+            Lang::En => format!(
+                "Usage: /setlink <code> [hours]. The code is {min_len} to {max_len} latin letters, digits, _ or -, \
+                starting with a letter. Hours is how long the old link keeps working (at most {max_hours})."
+            ),
+            Lang::Uk => format!(
+                "Використання: /setlink <адреса> [години]. Адреса - від {min_len} до {max_len} латинських літер, цифр, _ або -, \
+                починається з літери. Години - скільки ще працюватиме старе посилання (не більше {max_hours})."
+            ),
+        }
+    }
+
+    pub fn link_code_reserved(self) -> &'static str {
+        match self {
+            Lang::Ru => "Этот адрес зарезервирован, выберите другой.",
+            Lang::En => "This code is reserved, choose another one.",
+            Lang::Uk => "Ця адреса зарезервована, оберіть іншу.",
+        }
+    }
+
+    pub fn link_code_taken(self) -> &'static str {
+        match self {
+            Lang::Ru => "Этот адрес уже занят, выберите другой.",
+            Lang::En => "This code is already taken, choose another one.",
+            Lang::Uk => "Ця адреса вже зайнята, оберіть іншу.",
+        }
+    }
+
+    pub fn link_reclaimed(self, link: &str) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Администраторы забрали адрес вашей ссылки, теперь она работает по адресу {link}. Ответы на полученные сообщения работают как раньше."
+            ),
+            Lang::En => format!(
+                "Administrators have taken back your link's code, it now works at {link}. Replies to received messages work as before."
+            ),
+            Lang::Uk => format!(
+                "Адміністратори забрали адресу вашого посилання, тепер воно працює за адресою {link}. Відповіді на отримані повідомлення працюють як раніше."
+            ),
+        }
+    }
+
+    pub fn links_header(self) -> &'static str {
+        match self {
+            Lang::Ru => "Ваши ссылки. Создать ещё одну с подписью, например для другого канала: /addlink <подпись>",
+            Lang::En => "Your links. Create another one with a label, e.g. for another channel: /addlink <label>",
+            Lang::Uk => "Ваші посилання. Створити ще одне з підписом, наприклад для іншого каналу: /addlink <підпис>",
+        }
+    }
+
+    pub fn main_link_label(self) -> &'static str {
+        match self {
+            Lang::Ru => "Основная",
+            Lang::En => "Main",
+            Lang::Uk => "Основне",
+        }
+    }
+
+    pub fn link_stats(self, label: &str, link: &str, opens: i32, received: u64) -> String {
+        match self {
+            Lang::Ru => format!("{label}: {link}\nпереходов: {opens}, сообщений: {received}"),
+            Lang::En => format!("{label}: {link}\nopens: {opens}, messages: {received}"),
+            Lang::Uk => format!("{label}: {link}\nпереходів: {opens}, повідомлень: {received}"),
+        }
+    }
+
+    pub fn disable_link_button(self, label: &str) -> String {
+        match self {
+            Lang::Ru => format!("Отключить «{label}»"),
+            Lang::En => format!("Disable \"{label}\""),
+            Lang::Uk => format!("Вимкнути «{label}»"),
+        }
+    }
+
+    pub fn add_link_usage(self, max_len: usize) -> String {
+        match self {
+            Lang::Ru => {
+                format!("Использование: /addlink <подпись>, подпись не длиннее {max_len} символов.")
+            }
+            Lang::En => {
+                format!("Usage: /addlink <label>, the label is at most {max_len} characters long.")
+            }
+            Lang::Uk => {
+                format!("Використання: /addlink <підпис>, підпис не довший за {max_len} символів.")
+            }
+        }
+    }
+
+    pub fn too_many_links(self, max: usize) -> String {
+        match self {
+            Lang::Ru => format!("У вас уже {max} ссылок, отключите ненужные в /links."),
+            Lang::En => format!("You already have {max} links, disable unused ones in /links."),
+            Lang::Uk => format!("У вас уже {max} посилань, вимкніть непотрібні в /links."),
+        }
+    }
+
+    pub fn link_created(self, label: &str, link: &str) -> String {
+        match self {
+            Lang::Ru => format!("Ссылка «{label}» создана: {link}. Статистика по ссылкам: /links"),
+            Lang::En => format!("Link \"{label}\" created: {link}. Link statistics: /links"),
+            Lang::Uk => {
+                format!("Посилання «{label}» створено: {link}. Статистика посилань: /links")
+            }
+        }
+    }
+
+    pub fn inbox_closed(self, note: Option<&str>, own_link: &str) -> String {
+        let text = match self {
+            Lang::Ru => "Получатель сейчас не принимает сообщения.",
+            Lang::En => "The recipient isn't accepting messages right now.",
+            Lang::Uk => "Одержувач зараз не приймає повідомлення.",
+        };
+        match note {
+            Some(note) => format!("{text}\n\n{note}\n\n{}", self.own_link(own_link)),
+            None => format!("{text} {}", self.own_link(own_link)),
+        }
+    }
+
+    pub fn closed_note_too_long(self, max_len: usize) -> String {
+        match self {
+            Lang::Ru => {
+                format!("Сообщение для отправителей должно быть не длиннее {max_len} символов.")
+            }
+            Lang::En => format!("The note for senders must be at most {max_len} characters long."),
+            Lang::Uk => {
+                format!("Повідомлення для відправників має бути не довшим за {max_len} символів.")
+            }
+        }
+    }
+
+    pub fn inbox_paused(self) -> &'static str {
+        match self {
+            Lang::Ru => "Вы больше не принимаете новые сообщения, отправители увидят, что приём закрыт. \
+                Текст для них можно задать так: /pause <текст>. Снова принимать сообщения: /resume",
+            Lang::En => "You no longer receive new messages, senders will see that your inbox is closed. \
+                You can leave them a note: /pause <text>. Receive messages again: /resume",
+            Lang::Uk => "Ви більше не приймаєте нові повідомлення, відправники побачать, що прийом закрито. \
+                Текст для них можна задати так: /pause <текст>. Знову приймати повідомлення: /resume",
+        }
+    }
+
+    pub fn inbox_resumed(self) -> &'static str {
+        match self {
+            Lang::Ru => "Вы снова принимаете сообщения.",
+            Lang::En => "You receive messages again.",
+            Lang::Uk => "Ви знову приймаєте повідомлення.",
+        }
+    }
+
+    pub fn inbox_resumed_with_schedule(self, schedule: &str) -> String {
+        match self {
+            Lang::Ru => format!("Вы снова принимаете сообщения по расписанию: {schedule}"),
+            Lang::En => format!("You receive messages again on schedule: {schedule}"),
+            Lang::Uk => format!("Ви знову приймаєте повідомлення за розкладом: {schedule}"),
+        }
+    }
+
+    pub fn schedule_usage(self, current: Option<&str>) -> String {
+        let usage = match self {
+            Lang::Ru => "Использование: /schedule <дни> <часы> <часовой пояс>, например \
+                /schedule mon-fri 09:00-18:00 Europe/Moscow или /schedule daily 20:00-02:00 Asia/Almaty. \
+                Часовой пояс указывается по базе IANA, например Europe/Berlin или UTC. \
+                Дни: mon, tue, wed, thu, fri, sat, sun, диапазоны через дефис, daily, weekdays, weekends. \
+                Отключить расписание: /schedule off",
+            Lang::En => "Usage: /schedule <days> <hours> <time zone>, e.g. \
+                /schedule mon-fri 09:00-18:00 Europe/Moscow or /schedule daily 20:00-02:00 Asia/Almaty. \
+                The time zone is an IANA name, like Europe/Berlin or UTC. \
+                Days: mon, tue, wed, thu, fri, sat, sun, ranges with a hyphen, daily, weekdays, weekends. \
+                Turn the schedule off: /schedule off",
+            Lang::Uk => "Використання: /schedule <дні> <години> <часовий пояс>, наприклад \
+                /schedule mon-fri 09:00-18:00 Europe/Kyiv або /schedule daily 20:00-02:00 Asia/Almaty. \
+                Часовий пояс вказується за базою IANA, наприклад Europe/Berlin або UTC. \
+                Дні: mon, tue, wed, thu, fri, sat, sun, діапазони через дефіс, daily, weekdays, weekends. \
+                Вимкнути розклад: /schedule off",
+        };
+        match (current, self) {
+            (Some(current), Lang::Ru) => format!("Текущее расписание: {current}\n\n{usage}"),
+            (Some(current), Lang::En) => format!("Current schedule: {current}\n\n{usage}"),
+            (Some(current), Lang::Uk) => format!("Поточний розклад: {current}\n\n{usage}"),
+            (None, _) => usage.to_owned(),
+        }
+    }
+
+    pub fn schedule_set(self, schedule: &str) -> String {
+        match self {
+            Lang::Ru => format!("Теперь вы принимаете сообщения только по расписанию: {schedule}"),
+            Lang::En => format!("You now receive messages only on schedule: {schedule}"),
+            Lang::Uk => format!("Тепер ви приймаєте повідомлення лише за розкладом: {schedule}"),
+        }
+    }
+
+    pub fn schedule_removed(self) -> &'static str {
+        match self {
+            Lang::Ru => "Расписание отключено, сообщения принимаются в любое время.",
+            Lang::En => "Schedule turned off, messages are accepted at any time.",
+            Lang::Uk => "Розклад вимкнено, повідомлення приймаються будь-коли.",
+        }
+    }
+
+    pub fn message_type(self, message_type: MessageType) -> &'static str {
+        match (message_type, self) {
+            (MessageType::Text, Lang::Ru) => "текст",
+            (MessageType::Text, Lang::En) => "text",
+            (MessageType::Text, Lang::Uk) => "текст",
+            (MessageType::Photo, Lang::Ru) => "фото",
+            (MessageType::Photo, Lang::En) => "photos",
+            (MessageType::Photo, Lang::Uk) => "фото",
+            (MessageType::Video, Lang::Ru) => "видео",
+            (MessageType::Video, Lang::En) => "videos",
+            (MessageType::Video, Lang::Uk) => "відео",
+            (MessageType::Gif, Lang::Ru) => "GIF",
+            (MessageType::Gif, Lang::En) => "GIFs",
+            (MessageType::Gif, Lang::Uk) => "GIF",
+            (MessageType::Sticker, Lang::Ru) => "стикеры",
+            (MessageType::Sticker, Lang::En) => "stickers",
+            (MessageType::Sticker, Lang::Uk) => "стікери",
+            (MessageType::Voice, Lang::Ru) => "голосовые",
+            (MessageType::Voice, Lang::En) => "voice messages",
+            (MessageType::Voice, Lang::Uk) => "голосові",
+            (MessageType::VideoNote, Lang::Ru) => "видеокружки",
+            (MessageType::VideoNote, Lang::En) => "video notes",
+            (MessageType::VideoNote, Lang::Uk) => "відеокружечки",
+            (MessageType::Audio, Lang::Ru) => "аудио",
+            (MessageType::Audio, Lang::En) => "audio",
+            (MessageType::Audio, Lang::Uk) => "аудіо",
+            (MessageType::Document, Lang::Ru) => "файлы",
+            (MessageType::Document, Lang::En) => "files",
+            (MessageType::Document, Lang::Uk) => "файли",
+            (MessageType::Other, Lang::Ru) => "остальное (опросы, геопозиции, контакты)",
+            (MessageType::Other, Lang::En) => "everything else (polls, locations, contacts)",
+            (MessageType::Other, Lang::Uk) => "решта (опитування, геопозиції, контакти)",
+        }
+    }
+
+    pub fn message_types(self, types: &[MessageType]) -> String {
+        types
+            .iter()
+            .map(|&t| self.message_type(t))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn type_not_accepted(self, accepted: &str) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Получатель не принимает такие сообщения, только: {accepted}. Отправьте что-нибудь другое."
+            ),
+            Lang::En => format!(
+                "The recipient doesn't accept this kind of message, only: {accepted}. Send something else."
+            ),
+            Lang::Uk => format!(
+                "Одержувач не приймає такі повідомлення, лише: {accepted}. Надішліть щось інше."
+            ),
+        }
+    }
+
+    pub fn text_too_long(self, max: u32) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Получатель принимает тексты не длиннее {max} символов. Сократите сообщение и отправьте снова."
+            ),
+            Lang::En => format!(
+                "The recipient accepts texts up to {max} characters long. Shorten your message and send it again."
+            ),
+            Lang::Uk => format!(
+                "Одержувач приймає тексти не довші за {max} символів. Скоротіть повідомлення й надішліть знову."
+            ),
+        }
+    }
+
+    pub fn accept_usage(self, current: Option<&str>) -> String {
+        let usage = match self {
+            Lang::Ru => "Использование: /accept <типы>, например /accept text photo.                 Типы: text, photo, video, gif, sticker, voice, video_note, audio, document, other.                 Принимать всё: /accept all",
+            Lang::En => "Usage: /accept <types>, e.g. /accept text photo.                 Types: text, photo, video, gif, sticker, voice, video_note, audio, document, other.                 Accept everything: /accept all",
+            Lang::Uk => "Використання: /accept <типи>, наприклад /accept text photo.                 Типи: text, photo, video, gif, sticker, voice, video_note, audio, document, other.                 Приймати все: /accept all",
+        };
+        match (current, self) {
+            (Some(current), Lang::Ru) => format!("Сейчас вы принимаете: {current}\n\n{usage}"),
+            (Some(current), Lang::En) => format!("You currently accept: {current}\n\n{usage}"),
+            (Some(current), Lang::Uk) => format!("Зараз ви приймаєте: {current}\n\n{usage}"),
+            (None, _) => usage.to_owned(),
+        }
+    }
+
+    pub fn accepted_types_set(self, types: &str) -> String {
+        match self {
+            Lang::Ru => format!("Теперь вы принимаете только: {types}"),
+            Lang::En => format!("You now accept only: {types}"),
+            Lang::Uk => format!("Тепер ви приймаєте лише: {types}"),
+        }
+    }
+
+    pub fn accepted_types_reset(self) -> &'static str {
+        match self {
+            Lang::Ru => "Теперь вы принимаете сообщения любого типа.",
+            Lang::En => "You now accept messages of any type.",
+            Lang::Uk => "Тепер ви приймаєте повідомлення будь-якого типу.",
+        }
+    }
+
+    pub fn max_length_usage(self, current: Option<u32>, max: u32) -> String {
+        let usage = match self {
+            Lang::Ru => format!(
+                "Использование: /maxlength <число от 1 до {max}>. Снять ограничение: /maxlength off"
+            ),
+            Lang::En => {
+                format!(
+                    "Usage: /maxlength <number from 1 to {max}>. Remove the limit: /maxlength off"
+                )
+            }
+            Lang::Uk => format!(
+                "Використання: /maxlength <число від 1 до {max}>. Зняти обмеження: /maxlength off"
+            ),
+        };
+        match (current, self) {
+            (Some(current), Lang::Ru) => {
+                format!("Сейчас ограничение {current} символов.\n\n{usage}")
+            }
+            (Some(current), Lang::En) => {
+                format!("The current limit is {current} characters.\n\n{usage}")
+            }
+            (Some(current), Lang::Uk) => format!("Зараз обмеження {current} символів.\n\n{usage}"),
+            (None, _) => usage,
+        }
+    }
+
+    pub fn max_length_set(self, max: u32) -> String {
+        match self {
+            Lang::Ru => format!("Теперь вы принимаете тексты и подписи не длиннее {max} символов."),
+            Lang::En => format!("You now accept texts and captions up to {max} characters long."),
+            Lang::Uk => format!("Тепер ви приймаєте тексти й підписи не довші за {max} символів."),
+        }
+    }
+
+    pub fn max_length_removed(self) -> &'static str {
+        match self {
+            Lang::Ru => "Ограничение длины текста снято.",
+            Lang::En => "Text length limit removed.",
+            Lang::Uk => "Обмеження довжини тексту знято.",
+        }
+    }
+
+    pub fn retention_usage(self, current: u32, max: u32) -> String {
+        match self {
+            Lang::Ru => format!(
+                "Сообщения, которые вы отправили и получили, хранятся {current} дн., после чего \
+                бот забывает, кто кому писал, и на них больше нельзя ответить.\n\n\
+                Использование: /retention <число дней от 1 до {max}>. Вернуть срок по умолчанию: /retention off"
+            ),
+            Lang::En => format!(
+                "Messages you sent and received are kept for {current} days, after that the bot \
+                forgets who wrote to whom and they can no longer be replied to.\n\n\
+                Usage: /retention <number of days from 1 to {max}>. Restore the default: /retention off"
+            ),
+            Lang::Uk => format!(
+                "Повідомлення, які ви надіслали й отримали, зберігаються {current} дн., після чого \
+                бот забуває, хто кому писав, і на них більше не можна відповісти.\n\n\
+                Використання: /retention <кількість днів від 1 до {max}>. Повернути строк за замовчуванням: /retention off"
+            ),
+        }
+    }
+
+    pub fn retention_set(self, days: u32) -> String {
+        match self {
+            Lang::Ru => format!("Теперь ваши сообщения хранятся {days} дн."),
+            Lang::En => format!("Your messages are now kept for {days} days."),
+            Lang::Uk => format!("Тепер ваші повідомлення зберігаються {days} дн."),
+        }
+    }
+
+    pub fn retention_reset(self, days: u32) -> String {
+        match self {
+            Lang::Ru => format!("Ваши сообщения снова хранятся {days} дн., как у всех."),
+            Lang::En => format!("Your messages are kept for the default {days} days again."),
+            Lang::Uk => format!("Ваші повідомлення знову зберігаються {days} дн., як у всіх."),
+        }
+    }
+
+    pub fn settings(
+        self,
+        schedule: Option<&str>,
+        accepted_types: Option<&str>,
+        max_text_length: Option<u32>,
+        filters: usize,
+        retention_days: u32,
+    ) -> String {
+        let (title, schedule_name, accepted_name, length_name, filters_name, none) = match self {
+            Lang::Ru => (
+                "Настройки. Нажмите на кнопку, чтобы переключить.",
+                "Расписание",
+                "Принимаемые сообщения",
+                "Максимальная длина текста",
+                "Фильтры",
+                "нет",
+            ),
+            Lang::En => (
+                "Settings. Press a button to switch it.",
+                "Schedule",
+                "Accepted messages",
+                "Maximum text length",
+                "Filters",
+                "none",
+            ),
+            Lang::Uk => (
+                "Налаштування. Натисніть кнопку, щоб перемкнути.",
+                "Розклад",
+                "Прийняті повідомлення",
+                "Максимальна довжина тексту",
+                "Фільтри",
+                "немає",
+            ),
+        };
+        let retention = match self {
+            Lang::Ru => format!("История сообщений: {retention_days} дн. — /retention"),
+            Lang::En => format!("Message history: {retention_days} days — /retention"),
+            Lang::Uk => format!("Історія повідомлень: {retention_days} дн. — /retention"),
+        };
+        let all = match self {
+            Lang::Ru => "все",
+            Lang::En => "all",
+            Lang::Uk => "усі",
+        };
+        let max_text_length = max_text_length.map(|max| max.to_string());
+        [
+            title.to_owned(),
+            String::new(),
+            format!("{schedule_name}: {} — /schedule", schedule.unwrap_or(none)),
+            format!(
+                "{accepted_name}: {} — /accept",
+                accepted_types.unwrap_or(all)
+            ),
+            format!(
+                "{length_name}: {} — /maxlength",
+                max_text_length.as_deref().unwrap_or(none)
+            ),
+            format!("{filters_name}: {filters} — /filters"),
+            retention,
+        ]
+        .join("\n")
+    }
+
+    pub fn setting_name(self, setting: Setting) -> &'static str {
+        match (setting, self) {
+            (Setting::AnswerTip, Lang::Ru) => "Кнопка «Ответить»",
+            (Setting::AnswerTip, Lang::En) => "\"Reply\" button",
+            (Setting::AnswerTip, Lang::Uk) => "Кнопка «Відповісти»",
+            (Setting::Inbox, Lang::Ru) => "Приём сообщений",
+            (Setting::Inbox, Lang::En) => "Receiving messages",
+            (Setting::Inbox, Lang::Uk) => "Прийом повідомлень",
+        }
+    }
+
+    pub fn setting_button(self, setting: Setting, on: bool) -> String {
+        let state = match (on, self) {
+            (true, Lang::Ru) => "✅ вкл.",
+            (true, Lang::En) => "✅ on",
+            (true, Lang::Uk) => "✅ увімк.",
+            (false, Lang::Ru) => "❌ выкл.",
+            (false, Lang::En) => "❌ off",
+            (false, Lang::Uk) => "❌ вимк.",
+        };
+        format!("{}: {state}", self.setting_name(setting))
+    }
+
+    pub fn language_button(self) -> String {
+        let text = match self {
+            Lang::Ru => "Язык",
+            Lang::En => "Language",
+            Lang::Uk => "Мова",
+        };
+        format!("{text}: {}", self.name())
+    }
+
+    pub fn filters_header(self) -> &'static str {
+        match self {
+            Lang::Ru => "Ваши фильтры:",
+            Lang::En => "Your filters:",
+            Lang::Uk => "Ваші фільтри:",
+        }
+    }
+
+    pub fn no_filters(self) -> &'static str {
+        match self {
+            Lang::Ru => "У вас нет фильтров.",
+            Lang::En => "You have no filters.",
+            Lang::Uk => "У вас немає фільтрів.",
+        }
+    }
+
+    pub fn filter_usage(self) -> &'static str {
+        match self {
+            Lang::Ru => {
+                "Добавить фильтр: /filter <действие> <тип> [образец]\n\
+                Действия: drop — удалить, hold — задержать до проверки в /held, \
+                warn — доставить с предупреждением.\n\
+                Типы: word — слово или фраза, regex — регулярное выражение, \
+                url — любая ссылка, mention — любое @упоминание.\n\
+                Например: /filter hold regex \\+?\\d{10,} или /filter drop url"
+            }
+            Lang::En => {
+                "Add a filter: /filter <action> <type> [pattern]\n\
+                Actions: drop — discard, hold — keep for review in /held, \
+                warn — deliver with a warning.\n\
+                Types: word — a word or phrase, regex — a regular expression, \
+                url — any link, mention — any @mention.\n\
+                E.g. /filter hold regex \\+?\\d{10,} or /filter drop url"
+            }
+            Lang::Uk => {
+                "Додати фільтр: /filter <дія> <тип> [зразок]\n\
+                Дії: drop — видалити, hold — затримати до перевірки в /held, \
+                warn — доставити з попередженням.\n\
+                Типи: word — слово або фраза, regex — регулярний вираз, \
+                url — будь-яке посилання, mention — будь-яка @згадка.\n\
+                Наприклад: /filter hold regex \\+?\\d{10,} або /filter drop url"
+            }
+        }
+    }
+
+    pub fn too_many_filters(self, max: usize) -> String {
+        match self {
+            Lang::Ru => format!("У вас уже {max} фильтров, удалите ненужные в /filters."),
+            Lang::En => format!("You already have {max} filters, remove unused ones in /filters."),
+            Lang::Uk => format!("У вас уже {max} фільтрів, видаліть непотрібні в /filters."),
+        }
+    }
+
+    pub fn filter_added(self, filter: &str) -> String {
+        match self {
+            Lang::Ru => format!("Фильтр добавлен: {filter}. Все фильтры: /filters"),
+            Lang::En => format!("Filter added: {filter}. All filters: /filters"),
+            Lang::Uk => format!("Фільтр додано: {filter}. Усі фільтри: /filters"),
+        }
+    }
+
+    pub fn remove_filter_button(self, filter: &str) -> String {
+        match self {
+            Lang::Ru => format!("Удалить «{filter}»"),
+            Lang::En => format!("Remove \"{filter}\""),
+            Lang::Uk => format!("Видалити «{filter}»"),
+        }
+    }
+
+    pub fn held_count(self, count: u64) -> String {
+        match self {
+            Lang::Ru => format!("Задержано сообщений: {count}. Проверить: /held"),
+            Lang::En => format!("Held messages: {count}. Review them: /held"),
+            Lang::Uk => format!("Затримано повідомлень: {count}. Перевірити: /held"),
+        }
+    }
+
+    pub fn message_held(self) -> &'static str {
+        match self {
+            Lang::Ru => "Новое сообщение задержано вашими фильтрами. Проверить: /held",
+            Lang::En => "A new message was held by your filters. Review it: /held",
+            Lang::Uk => "Нове повідомлення затримано вашими фільтрами. Перевірити: /held",
+        }
+    }
+
+    pub fn filter_warning(self, filter: &str) -> String {
+        match self {
+            Lang::Ru => format!("⚠️ Это сообщение попало под фильтр: {filter}"),
+            Lang::En => format!("⚠️ This message matches your filter: {filter}"),
+            Lang::Uk => format!("⚠️ Це повідомлення потрапило під фільтр: {filter}"),
+        }
+    }
+
+    pub fn no_held_messages(self) -> &'static str {
+        match self {
+            Lang::Ru => "Задержанных сообщений нет.",
+            Lang::En => "There are no held messages.",
+            Lang::Uk => "Затриманих повідомлень немає.",
+        }
+    }
+
+    pub fn held_message_info(self, filter: &str, count: u64) -> String {
+        match self {
+            Lang::Ru => format!("Задержано фильтром: {filter}\nВсего задержано: {count}"),
+            Lang::En => format!("Held by filter: {filter}\nHeld in total: {count}"),
+            Lang::Uk => format!("Затримано фільтром: {filter}\nУсього затримано: {count}"),
+        }
+    }
+
+    pub fn deliver_held_button(self) -> &'static str {
+        match self {
+            Lang::Ru => "✅ Доставить",
+            Lang::En => "✅ Deliver",
+            Lang::Uk => "✅ Доставити",
+        }
+    }
+
+    pub fn delete_held_button(self) -> &'static str {
+        match self {
+            Lang::Ru => "🗑 Удалить",
+            Lang::En => "🗑 Delete",
+            Lang::Uk => "🗑 Видалити",
+        }
+    }
+
+    pub fn held_delivery_failed(self, error: &str) -> String {
+        match self {
+            Lang::Ru => format!("Не удалось доставить сообщение: {error}"),
+            Lang::En => format!("Couldn't deliver the message: {error}"),
+            Lang::Uk => format!("Не вдалося доставити повідомлення: {error}"),
+        }
+    }
+
+    fn wait_time(self, seconds: u64) -> String {
+        match (self, seconds < 60) {
+            (Lang::Ru, true) => format!("{seconds} сек."),
+            (Lang::Ru, false) => format!("{} мин.", seconds.div_ceil(60)),
+            (Lang::En, true) => format!("{seconds} s"),
+            (Lang::En, false) => format!("{} min", seconds.div_ceil(60)),
+            (Lang::Uk, true) => format!("{seconds} с"),
+            (Lang::Uk, false) => format!("{} хв", seconds.div_ceil(60)),
+        }
+    }
+
+    pub fn throttled(self, scope: LimitScope, wait: u64) -> String {
+        let wait = self.wait_time(wait);
+        match (scope, self) {
+            (LimitScope::Sender, Lang::Ru) => {
+                format!("Вы отправляете слишком много сообщений. Попробуйте снова через {wait}")
+            }
+            (LimitScope::Sender, Lang::En) => {
+                format!("You are sending too many messages. Try again in {wait}")
+            }
+            (LimitScope::Sender, Lang::Uk) => {
+                format!("Ви надсилаєте забагато повідомлень. Спробуйте знову через {wait}")
+            }
+            (LimitScope::Pair, Lang::Ru) => format!(
+                "Вы отправили этому получателю слишком много сообщений подряд. Попробуйте снова через {wait}"
+            ),
+            (LimitScope::Pair, Lang::En) => format!(
+                "You have sent too many messages to this recipient in a row. Try again in {wait}"
+            ),
+            (LimitScope::Pair, Lang::Uk) => format!(
+                "Ви надіслали цьому одержувачу забагато повідомлень поспіль. Спробуйте знову через {wait}"
+            ),
+            (LimitScope::Recipient, Lang::Ru) => format!(
+                "Получателю сейчас пишут слишком много. Попробуйте снова через {wait}"
+            ),
+            (LimitScope::Recipient, Lang::En) => format!(
+                "The recipient is getting too many messages right now. Try again in {wait}"
+            ),
+            (LimitScope::Recipient, Lang::Uk) => format!(
+                "Одержувачу зараз пишуть забагато. Спробуйте знову через {wait}"
+            ),
+        }
+    }
+
+    pub fn report_button(self) -> &'static str {
+        match self {
+            Lang::Ru => "⚠️ Пожаловаться",
+            Lang::En => "⚠️ Report",
+            Lang::Uk => "⚠️ Поскаржитися",
+        }
+    }
+
+    pub fn choose_report_reason(self) -> &'static str {
+        match self {
+            Lang::Ru => "Почему вы жалуетесь на это сообщение?",
+            Lang::En => "Why are you reporting this message?",
+            Lang::Uk => "Чому ви скаржитеся на це повідомлення?",
+        }
+    }
+
+    pub fn report_reason(self, reason: ReportReason) -> &'static str {
+        match (reason, self) {
+            (ReportReason::Spam, Lang::Ru) => "Спам",
+            (ReportReason::Spam, Lang::En) => "Spam",
+            (ReportReason::Spam, Lang::Uk) => "Спам",
+            (ReportReason::Abuse, Lang::Ru) => "Оскорбления или угрозы",
+            (ReportReason::Abuse, Lang::En) => "Insults or threats",
+            (ReportReason::Abuse, Lang::Uk) => "Образи або погрози",
+            (ReportReason::Other, Lang::Ru) => "Другое",
+            (ReportReason::Other, Lang::En) => "Other",
+            (ReportReason::Other, Lang::Uk) => "Інше",
+        }
+    }
+
+    pub fn report_sent(self) -> &'static str {
+        match self {
+            Lang::Ru => "Жалоба отправлена модераторам. Мы сообщим, когда её рассмотрят.",
+            Lang::En => {
+                "The report has been sent to moderators. We'll let you know once it's reviewed."
+            }
+            Lang::Uk => "Скаргу надіслано модераторам. Ми повідомимо, коли її розглянуть.",
+        }
+    }
+
+    pub fn already_reported(self) -> &'static str {
+        match self {
+            Lang::Ru => "Вы уже пожаловались на это сообщение.",
+            Lang::En => "You have already reported this message.",
+            Lang::Uk => "Ви вже поскаржилися на це повідомлення.",
+        }
+    }
+
+    pub fn report_failed(self) -> &'static str {
+        match self {
+            Lang::Ru => "Не удалось найти сообщение, на которое вы жалуетесь.",
+            Lang::En => "Couldn't find the message you are reporting.",
+            Lang::Uk => "Не вдалося знайти повідомлення, на яке ви скаржитеся.",
+        }
+    }
+
+    pub fn report_reviewed(self, decision: ReportStatus) -> &'static str {
+        match (decision, self) {
+            (ReportStatus::Open | ReportStatus::Dismissed, Lang::Ru) => {
+                "Модераторы рассмотрели вашу жалобу и не нашли нарушений."
+            }
+            (ReportStatus::Open | ReportStatus::Dismissed, Lang::En) => {
+                "Moderators have reviewed your report and found no violation."
+            }
+            (ReportStatus::Open | ReportStatus::Dismissed, Lang::Uk) => {
+                "Модератори розглянули вашу скаргу й не знайшли порушень."
+            }
+            (ReportStatus::Warned, Lang::Ru) => {
+                "Модераторы рассмотрели вашу жалобу, отправитель получил предупреждение."
+            }
+            (ReportStatus::Warned, Lang::En) => {
+                "Moderators have reviewed your report, the sender has been warned."
+            }
+            (ReportStatus::Warned, Lang::Uk) => {
+                "Модератори розглянули вашу скаргу, відправник отримав попередження."
+            }
+            (ReportStatus::Banned, Lang::Ru) => {
+                "Модераторы рассмотрели вашу жалобу, отправитель заблокирован в боте."
+            }
+            (ReportStatus::Banned, Lang::En) => {
+                "Moderators have reviewed your report, the sender has been banned from the bot."
+            }
+            (ReportStatus::Banned, Lang::Uk) => {
+                "Модератори розглянули вашу скаргу, відправника заблоковано в боті."
+            }
+        }
+    }
+
+    pub fn sender_warned(self) -> &'static str {
+        match self {
+            Lang::Ru => "На это сообщение пожаловались, и модераторы признали его нарушением. При повторных нарушениях вы будете заблокированы в боте.",
+            Lang::En => "This message was reported and moderators found it to be a violation. Further violations will get you banned from the bot.",
+            Lang::Uk => "На це повідомлення поскаржилися, і модератори визнали його порушенням. У разі повторних порушень вас буде заблоковано в боті.",
+        }
+    }
+
+    pub fn banned(self, reason: Option<&str>, until: Option<&str>) -> String {
+        let mut text = match (self, until) {
+            (Lang::Ru, Some(until)) => format!("Вы заблокированы в боте до {until}."),
+            (Lang::Ru, None) => "Вы заблокированы в боте.".to_owned(),
+            (Lang::En, Some(until)) => format!("You are banned from the bot until {until}."),
+            (Lang::En, None) => "You are banned from the bot.".to_owned(),
+            (Lang::Uk, Some(until)) => format!("Вас заблоковано в боті до {until}."),
+            (Lang::Uk, None) => "Вас заблоковано в боті.".to_owned(),
+        };
+        if let Some(reason) = reason {
+            let reason = match self {
+                Lang::Ru => format!(" Причина: {reason}"),
+                Lang::En => format!(" Reason: {reason}"),
+                Lang::Uk => format!(" Причина: {reason}"),
+            };
+            text.push_str(&reason);
+        }
+        text
+    }
+}
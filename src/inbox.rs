@@ -0,0 +1,375 @@
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use teloxide::prelude::*;
+
+use crate::{db::Db, i18n::Lang, Bot};
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const MAX_NOTE_LEN: usize = 500;
+/// Telegram doesn't allow longer texts anyway.
+const MAX_TEXT_LENGTH: u32 = 4096;
+
+/// Weekly open hours in the recipient's local time.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Days the inbox opens on, Monday is 0.
+    pub days: Vec<u32>,
+    /// Minutes since local midnight, `to` before `from` means the hours span midnight.
+    pub from: u32,
+    pub to: u32,
+    /// The recipient's time zone, so that open hours follow daylight saving time.
+    pub timezone: Tz,
+}
+
+impl Schedule {
+    pub fn is_open(&self, now_utc: NaiveDateTime) -> bool {
+        let local = now_utc.and_utc().with_timezone(&self.timezone);
+        let day = local.weekday().num_days_from_monday();
+        let minutes = local.hour() * 60 + local.minute();
+        if self.from <= self.to {
+            self.days.contains(&day) && (self.from..self.to).contains(&minutes)
+        } else {
+            let yesterday = (day + 6) % 7;
+            (self.days.contains(&day) && minutes >= self.from)
+                || (self.days.contains(&yesterday) && minutes < self.to)
+        }
+    }
+
+    /// Parses `<days> <HH:MM>-<HH:MM> <time zone>`, e.g. `mon-fri,sun 09:00-18:00 Europe/Moscow`.
+    pub fn parse(args: &str) -> Result<Self> {
+        let [days, hours, timezone] = args.split_whitespace().collect::<Vec<_>>()[..] else {
+            bail!("expected days, hours and time zone");
+        };
+        let (from, to) = hours
+            .split_once('-')
+            .context("hours look like 09:00-18:00")?;
+        let (from, to) = (parse_time(from)?, parse_time(to)?);
+        if from == to {
+            bail!("open hours can't be empty");
+        }
+        Ok(Schedule {
+            days: parse_days(days)?,
+            from,
+            to,
+            timezone: Tz::from_str_insensitive(timezone)
+                .map_err(|_| anyhow!("unknown time zone: {timezone}"))?,
+        })
+    }
+
+    /// Formats the schedule the way `parse` accepts it.
+    pub fn describe(&self) -> String {
+        let days = self
+            .days
+            .iter()
+            .map(|&d| WEEKDAYS[d as usize])
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{days} {:02}:{:02}-{:02}:{:02} {}",
+            self.from / 60,
+            self.from % 60,
+            self.to / 60,
+            self.to % 60,
+            self.timezone
+        )
+    }
+}
+
+fn parse_days(days: &str) -> Result<Vec<u32>> {
+    let weekday = |name: &str| {
+        WEEKDAYS
+            .iter()
+            .position(|d| d.eq_ignore_ascii_case(name))
+            .map(|d| d as u32)
+            .context(format!("unknown day: {name}"))
+    };
+
+    let mut result = Vec::new();
+    for part in days.split(',') {
+        match part.to_lowercase().as_str() {
+            "daily" => result.extend(0..7),
+            "weekdays" => result.extend(0..5),
+            "weekends" => result.extend(5..7),
+            part => match part.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last) = (weekday(first)?, weekday(last)?);
+                    // ranges like sat-mon wrap around the week
+                    let mut day = first;
+                    loop {
+                        result.push(day);
+                        if day == last {
+                            break;
+                        }
+                        day = (day + 1) % 7;
+                    }
+                }
+                None => result.push(weekday(part)?),
+            },
+        }
+    }
+    result.sort_unstable();
+    result.dedup();
+    Ok(result)
+}
+
+fn parse_time(time: &str) -> Result<u32> {
+    let (hours, minutes) = time.split_once(':').unwrap_or((time, "0"));
+    let (hours, minutes): (u32, u32) = (hours.parse()?, minutes.parse()?);
+    if hours > 24 || minutes > 59 || (hours == 24 && minutes > 0) {
+        bail!("invalid time: {time}");
+    }
+    Ok(hours * 60 + minutes)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageType {
+    Text,
+    Photo,
+    Video,
+    /// GIFs and other animations without sound.
+    Gif,
+    Sticker,
+    Voice,
+    VideoNote,
+    Audio,
+    Document,
+    /// Polls, locations, contacts, dice and the like.
+    Other,
+}
+
+impl MessageType {
+    pub const ALL: [MessageType; 10] = [
+        MessageType::Text,
+        MessageType::Photo,
+        MessageType::Video,
+        MessageType::Gif,
+        MessageType::Sticker,
+        MessageType::Voice,
+        MessageType::VideoNote,
+        MessageType::Audio,
+        MessageType::Document,
+        MessageType::Other,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageType::Text => "text",
+            MessageType::Photo => "photo",
+            MessageType::Video => "video",
+            MessageType::Gif => "gif",
+            MessageType::Sticker => "sticker",
+            MessageType::Voice => "voice",
+            MessageType::VideoNote => "video_note",
+            MessageType::Audio => "audio",
+            MessageType::Document => "document",
+            MessageType::Other => "other",
+        }
+    }
+
+    pub fn of(msg: &Message) -> Self {
+        if msg.text().is_some() {
+            MessageType::Text
+        } else if msg.photo().is_some() {
+            MessageType::Photo
+        } else if msg.video().is_some() {
+            MessageType::Video
+        } else if msg.animation().is_some() {
+            MessageType::Gif
+        } else if msg.sticker().is_some() {
+            MessageType::Sticker
+        } else if msg.voice().is_some() {
+            MessageType::Voice
+        } else if msg.video_note().is_some() {
+            MessageType::VideoNote
+        } else if msg.audio().is_some() {
+            MessageType::Audio
+        } else if msg.document().is_some() {
+            MessageType::Document
+        } else {
+            MessageType::Other
+        }
+    }
+}
+
+impl FromStr for MessageType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_lowercase();
+        MessageType::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .context(format!("unknown message type: {s}"))
+    }
+}
+
+/// Why a recipient doesn't accept a message.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Rejection {
+    Type(Vec<MessageType>),
+    TooLong(u32),
+}
+
+/// Whether a recipient currently accepts new messages and which ones.
+#[derive(Clone, Debug, Default)]
+pub struct Inbox {
+    pub paused: bool,
+    /// Shown to senders while the inbox is closed.
+    pub note: Option<String>,
+    pub schedule: Option<Schedule>,
+    /// `None` means any type is accepted.
+    pub accepted_types: Option<Vec<MessageType>>,
+    /// Longest accepted text or caption in characters.
+    pub max_text_length: Option<u32>,
+}
+
+impl Inbox {
+    pub fn is_open(&self) -> bool {
+        let now = chrono::Utc::now().naive_utc();
+        !self.paused && self.schedule.as_ref().is_none_or(|s| s.is_open(now))
+    }
+
+    pub fn rejects(&self, msg: &Message) -> Option<Rejection> {
+        if let Some(accepted) = &self.accepted_types
+            && !accepted.contains(&MessageType::of(msg))
+        {
+            return Some(Rejection::Type(accepted.clone()));
+        }
+        let text = msg.text().or(msg.caption()).unwrap_or_default();
+        match self.max_text_length {
+            Some(max) if text.chars().count() > max as usize => Some(Rejection::TooLong(max)),
+            _ => None,
+        }
+    }
+}
+
+pub async fn handle_command_pause(
+    bot: Bot,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+    note: String,
+) -> Result<()> {
+    let note = note.trim();
+    if note.chars().count() > MAX_NOTE_LEN {
+        bot.send_message(msg.chat.id, lang.closed_note_too_long(MAX_NOTE_LEN))
+            .await?;
+        return Ok(());
+    }
+
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let note = (!note.is_empty()).then(|| note.to_owned());
+    db.set_paused(msg.chat.id.0, true, note).await?;
+    bot.send_message(msg.chat.id, lang.inbox_paused()).await?;
+    Ok(())
+}
+
+pub async fn handle_command_resume(bot: Bot, db: Arc<Db>, msg: Message, lang: Lang) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    db.set_paused(msg.chat.id.0, false, None).await?;
+    let inbox = db.get_inbox(msg.chat.id.0).await?;
+    let text = match inbox.schedule {
+        Some(schedule) => lang.inbox_resumed_with_schedule(&schedule.describe()),
+        None => lang.inbox_resumed().to_owned(),
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+pub async fn handle_command_schedule(
+    bot: Bot,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+    args: String,
+) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let args = args.trim();
+    let text = if args.is_empty() {
+        let current = db.get_inbox(msg.chat.id.0).await?.schedule;
+        lang.schedule_usage(current.map(|s| s.describe()).as_deref())
+    } else if args.eq_ignore_ascii_case("off") {
+        db.set_schedule(msg.chat.id.0, None).await?;
+        lang.schedule_removed().to_owned()
+    } else {
+        match Schedule::parse(args) {
+            Ok(schedule) => {
+                let text = lang.schedule_set(&schedule.describe());
+                db.set_schedule(msg.chat.id.0, Some(&schedule)).await?;
+                text
+            }
+            Err(_) => lang.schedule_usage(None),
+        }
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+pub async fn handle_command_accept(
+    bot: Bot,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+    args: String,
+) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let args = args.trim();
+    let text = if args.is_empty() {
+        let current = db.get_inbox(msg.chat.id.0).await?.accepted_types;
+        lang.accept_usage(current.map(|types| lang.message_types(&types)).as_deref())
+    } else if args.eq_ignore_ascii_case("all") {
+        db.set_accepted_types(msg.chat.id.0, None).await?;
+        lang.accepted_types_reset().to_owned()
+    } else {
+        let types = args
+            .split([' ', ','])
+            .filter(|t| !t.is_empty())
+            .map(MessageType::from_str)
+            .collect::<Result<Vec<_>>>();
+        match types {
+            // only separators would leave nothing accepted
+            Ok(mut types) if !types.is_empty() => {
+                types.sort_by_key(|t| MessageType::ALL.iter().position(|a| a == t));
+                types.dedup();
+                db.set_accepted_types(msg.chat.id.0, Some(&types)).await?;
+                lang.accepted_types_set(&lang.message_types(&types))
+            }
+            _ => lang.accept_usage(None),
+        }
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+pub async fn handle_command_max_length(
+    bot: Bot,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+    args: String,
+) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let args = args.trim();
+    let text = if args.is_empty() {
+        let current = db.get_inbox(msg.chat.id.0).await?.max_text_length;
+        lang.max_length_usage(current, MAX_TEXT_LENGTH)
+    } else if args.eq_ignore_ascii_case("off") {
+        db.set_max_text_length(msg.chat.id.0, None).await?;
+        lang.max_length_removed().to_owned()
+    } else {
+        match args.parse::<u32>() {
+            Ok(max) if (1..=MAX_TEXT_LENGTH).contains(&max) => {
+                db.set_max_text_length(msg.chat.id.0, Some(max)).await?;
+                lang.max_length_set(max)
+            }
+            _ => lang.max_length_usage(None, MAX_TEXT_LENGTH),
+        }
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, Me},
+};
+
+use crate::{db::Db, i18n::Lang, Bot, UserLink};
+
+/// Longest time an old link may keep working after /newlink.
+pub const MAX_LINK_GRACE_HOURS: u32 = 7 * 24;
+/// How many enabled links a user may have, the main one included.
+const MAX_LINKS: usize = 10;
+const MAX_LABEL_LEN: usize = 32;
+const MIN_CODE_LEN: usize = 4;
+const MAX_CODE_LEN: usize = 32;
+/// Custom codes that could be mistaken for the bot's own or official accounts.
+const RESERVED_CODES: &[&str] = &[
+    "admin",
+    "admins",
+    "administrator",
+    "anoquebot",
+    "bot",
+    "help",
+    "moderator",
+    "official",
+    "settings",
+    "start",
+    "support",
+    "telegram",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CodeError {
+    Invalid,
+    Reserved,
+    Taken,
+}
+
+/// Checks a lowercase custom code against Telegram's deep link rules (`A-Z`, `a-z`, `0-9`, `_`
+/// and `-`), starting with a letter so it can't be confused with a user id.
+pub async fn check_code(db: &Db, code: &str) -> Result<Result<(), CodeError>> {
+    let valid = (MIN_CODE_LEN..=MAX_CODE_LEN).contains(&code.len())
+        && code.starts_with(|c: char| c.is_ascii_lowercase())
+        && code
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !valid {
+        return Ok(Err(CodeError::Invalid));
+    }
+    if RESERVED_CODES.contains(&code) {
+        return Ok(Err(CodeError::Reserved));
+    }
+    if db.link_code_taken(code).await? {
+        return Ok(Err(CodeError::Taken));
+    }
+    Ok(Ok(()))
+}
+
+fn parse_grace(grace: Option<&str>) -> Option<u32> {
+    match grace.map(str::parse) {
+        None => Some(0),
+        Some(Ok(hours)) if hours <= MAX_LINK_GRACE_HOURS => Some(hours),
+        _ => None,
+    }
+}
+
+pub async fn handle_command_new_link(
+    bot: Bot,
+    me: Me,
+    msg: Message,
+    db: Arc<Db>,
+    lang: Lang,
+    grace: String,
+) -> Result<()> {
+    let grace = grace.trim();
+    let Some(grace_hours) = parse_grace(Some(grace).filter(|g| !g.is_empty())) else {
+        bot.send_message(msg.chat.id, lang.new_link_usage(MAX_LINK_GRACE_HOURS))
+            .await?;
+        return Ok(());
+    };
+
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let link = db.rotate_link(msg.chat.id.0, grace_hours, None).await?;
+    bot.send_message(msg.chat.id, lang.new_link(&link.tme_url(&me), grace_hours))
+        .await?;
+    Ok(())
+}
+
+pub async fn handle_command_set_link(
+    bot: Bot,
+    me: Me,
+    msg: Message,
+    db: Arc<Db>,
+    lang: Lang,
+    args: String,
+) -> Result<()> {
+    let mut args = args.split_whitespace();
+    let (Some(code), Some(grace_hours)) = (args.next(), parse_grace(args.next())) else {
+        bot.send_message(
+            msg.chat.id,
+            lang.set_link_usage(MIN_CODE_LEN, MAX_CODE_LEN, MAX_LINK_GRACE_HOURS),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let code = code.to_lowercase();
+    if let Err(e) = check_code(&db, &code).await? {
+        let text = match e {
+            CodeError::Invalid => {
+                lang.set_link_usage(MIN_CODE_LEN, MAX_CODE_LEN, MAX_LINK_GRACE_HOURS)
+            }
+            CodeError::Reserved => lang.link_code_reserved().to_owned(),
+            CodeError::Taken => lang.link_code_taken().to_owned(),
+        };
+        bot.send_message(msg.chat.id, text).await?;
+        return Ok(());
+    }
+
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let link = db
+        .rotate_link(msg.chat.id.0, grace_hours, Some(code))
+        .await?;
+    bot.send_message(msg.chat.id, lang.new_link(&link.tme_url(&me), grace_hours))
+        .await?;
+    Ok(())
+}
+
+pub async fn links_message(
+    db: &Db,
+    me: &Me,
+    user_id: i64,
+    lang: Lang,
+) -> Result<(String, InlineKeyboardMarkup)> {
+    let links = db.get_links(user_id, false).await?;
+
+    let mut lines = vec![lang.links_header().to_owned()];
+    let mut buttons = Vec::new();
+    for (link, received) in &links {
+        let url = UserLink(link.code.clone()).tme_url(me);
+        let label = link.label.as_deref().unwrap_or(lang.main_link_label());
+        lines.push(lang.link_stats(label, &url, link.opens, *received));
+        if let Some(label) = &link.label {
+            buttons.push([InlineKeyboardButton::callback(
+                lang.disable_link_button(label),
+                format!("disable_link:{}", link.id),
+            )]);
+        }
+    }
+    Ok((lines.join("\n\n"), InlineKeyboardMarkup::new(buttons)))
+}
+
+pub async fn handle_command_links(
+    bot: Bot,
+    me: Me,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let (text, keyboard) = links_message(&db, &me, msg.chat.id.0, lang).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+pub async fn handle_command_add_link(
+    bot: Bot,
+    me: Me,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+    label: String,
+) -> Result<()> {
+    let label = label.trim();
+    if label.is_empty() || label.chars().count() > MAX_LABEL_LEN {
+        bot.send_message(msg.chat.id, lang.add_link_usage(MAX_LABEL_LEN))
+            .await?;
+        return Ok(());
+    }
+
+    db.get_user_link(msg.chat.id.0, None).await?;
+    if db.get_links(msg.chat.id.0, false).await?.len() >= MAX_LINKS {
+        bot.send_message(msg.chat.id, lang.too_many_links(MAX_LINKS))
+            .await?;
+        return Ok(());
+    }
+
+    let link = db
+        .create_link(msg.chat.id.0, Some(label.to_owned()))
+        .await?;
+    bot.send_message(
+        msg.chat.id,
+        lang.link_created(label, &UserLink(link.code).tme_url(&me)),
+    )
+    .await?;
+    Ok(())
+}
@@ -2,11 +2,12 @@
 
 use std::{str::FromStr, sync::Arc};
 
-use anyhow::{ensure, Context, Result};
+use anyhow::{bail, ensure, Context, Result};
 use dptree::case;
+use serde::{Deserialize, Serialize};
 use teloxide::{
     adaptors::{throttle::Limits, CacheMe, Throttle},
-    dispatching::dialogue::{GetChatId, InMemStorage},
+    dispatching::dialogue::GetChatId,
     macros::BotCommands,
     payloads::{AnswerCallbackQuerySetters, CopyMessageSetters},
     prelude::*,
@@ -19,17 +20,49 @@ use teloxide::{
 use tracing::*;
 use tracing_subscriber::prelude::*;
 
+mod account;
+mod admin;
+mod albums;
+mod ban;
+mod broadcast;
+mod conversations;
 mod db;
-
+mod edits;
+mod export;
+mod filters;
+mod i18n;
+mod inbox;
+mod links;
+mod moderation;
+mod rate_limit;
+mod reactions;
+mod received;
+mod retention;
+mod settings;
+mod unsend;
+
+use admin::{admin_callback_handler, admin_command_handler};
+use albums::{album_handler, Albums};
+use ban::banned_user_handler;
 use db::Db;
-
-#[derive(Clone)]
+use edits::edited_message_handler;
+use filters::FilterAction;
+use i18n::Lang;
+use inbox::Rejection;
+use moderation::{moderation_callback_handler, ReportReason};
+use reactions::reaction_handler;
+use settings::Setting;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WaitNewMessage {
     recipient_id: i64,
     clear_markup_message_id: i32,
+    /// Link the sender came through, absent in dialogues started before links were tracked.
+    #[serde(default)]
+    link_id: Option<i32>,
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub enum State {
     #[default]
     Start,
@@ -37,7 +70,7 @@ pub enum State {
 }
 
 type Bot = CacheMe<Throttle<teloxide::Bot>>;
-type MyDialogue = Dialogue<State, InMemStorage<State>>;
+type MyDialogue = Dialogue<State, Db>;
 
 #[derive(Clone)]
 struct UserLink(pub String);
@@ -50,11 +83,63 @@ impl UserLink {
     }
 }
 
+/// Shared by the handlers of regular messages, `link_url` is the sender's own link.
+#[derive(Clone)]
+struct MessageContext {
+    bot: Bot,
+    db: Arc<Db>,
+    link_url: String,
+    lang: Lang,
+}
+
 #[derive(BotCommands, PartialEq, Debug, Clone)]
 #[command(rename_rule = "lowercase")]
 enum Command {
     #[command(description = "Получить свою ссылку")]
     Start(String),
+    #[command(description = "Сменить язык")]
+    Language,
+    #[command(description = "Заблокированные отправители")]
+    Blocked,
+    #[command(description = "Сменить ссылку")]
+    NewLink(String),
+    #[command(description = "Задать свой адрес ссылки")]
+    SetLink(String),
+    #[command(description = "Мои ссылки и статистика")]
+    Links,
+    #[command(description = "Создать ссылку с подписью")]
+    AddLink(String),
+    #[command(description = "Перестать принимать сообщения")]
+    Pause(String),
+    #[command(description = "Снова принимать сообщения")]
+    Resume,
+    #[command(description = "Часы приёма сообщений")]
+    Schedule(String),
+    #[command(description = "Какие сообщения принимать")]
+    Accept(String),
+    #[command(description = "Максимальная длина текста")]
+    MaxLength(String),
+    #[command(description = "Фильтры сообщений")]
+    Filters,
+    #[command(description = "Добавить фильтр")]
+    Filter(String),
+    #[command(description = "Задержанные фильтрами сообщения")]
+    Held,
+    #[command(description = "Настройки")]
+    Settings,
+    #[command(description = "Удалить своё сообщение у получателя (ответом на него)")]
+    Unsend,
+    #[command(description = "Полученные сообщения")]
+    Inbox,
+    #[command(description = "Выгрузить свои данные")]
+    Export,
+    #[command(
+        rename = "delete_me",
+        description = "Удалить свой аккаунт и все данные"
+    )]
+    DeleteMe,
+    #[command(description = "Сколько хранить историю сообщений")]
+    Retention(String),
 }
 
 fn main() -> Result<()> {
@@ -111,35 +196,106 @@ async fn _main() -> Result<()> {
         .cache_me();
 
     let command_handler = teloxide::filter_command::<Command, _>()
-        .branch(case![Command::Start(link)].endpoint(handle_command_start));
+        .branch(case![Command::Start(link)].endpoint(handle_command_start))
+        .branch(case![Command::Language].endpoint(handle_command_language))
+        .branch(case![Command::Blocked].endpoint(handle_command_blocked))
+        .branch(case![Command::NewLink(grace)].endpoint(links::handle_command_new_link))
+        .branch(case![Command::SetLink(args)].endpoint(links::handle_command_set_link))
+        .branch(case![Command::Links].endpoint(links::handle_command_links))
+        .branch(case![Command::AddLink(label)].endpoint(links::handle_command_add_link))
+        .branch(case![Command::Pause(note)].endpoint(inbox::handle_command_pause))
+        .branch(case![Command::Resume].endpoint(inbox::handle_command_resume))
+        .branch(case![Command::Schedule(args)].endpoint(inbox::handle_command_schedule))
+        .branch(case![Command::Accept(args)].endpoint(inbox::handle_command_accept))
+        .branch(case![Command::MaxLength(args)].endpoint(inbox::handle_command_max_length))
+        .branch(case![Command::Filters].endpoint(filters::handle_command_filters))
+        .branch(case![Command::Filter(args)].endpoint(filters::handle_command_filter))
+        .branch(case![Command::Held].endpoint(filters::handle_command_held))
+        .branch(case![Command::Settings].endpoint(settings::handle_command_settings))
+        .branch(case![Command::Unsend].endpoint(unsend::handle_command_unsend))
+        .branch(case![Command::Inbox].endpoint(received::handle_command_inbox))
+        .branch(case![Command::Export].endpoint(export::handle_command_export))
+        .branch(case![Command::DeleteMe].endpoint(account::handle_command_delete_me))
+        .branch(case![Command::Retention(args)].endpoint(retention::handle_command_retention));
 
     let message_handler = Update::filter_message()
+        .branch(admin_command_handler())
         .branch(command_handler)
-        .map_async(|db: Arc<Db>, msg: Message| async move {
-            db.get_user_link(msg.chat.id.0, None)
-                .await
-                .unwrap_or(UserLink("ERROR".to_owned()))
-        })
+        .map_async(
+            |bot: Bot, db: Arc<Db>, me: Me, msg: Message, lang: Lang| async move {
+                let user_link = db
+                    .get_user_link(msg.chat.id.0, None)
+                    .await
+                    .unwrap_or(UserLink("ERROR".to_owned()));
+                MessageContext {
+                    bot,
+                    link_url: user_link.tme_url(&me),
+                    db,
+                    lang,
+                }
+            },
+        )
+        .branch(album_handler())
         .branch(case![State::WaitNewMessage(wait_new_message)].endpoint(handle_state_wait))
         .branch(dptree::endpoint(handle_state_start));
 
-    let callback_handler = Update::filter_callback_query().endpoint(handle_callback_query);
+    let callback_handler = Update::filter_callback_query()
+        .branch(admin_callback_handler())
+        .branch(moderation_callback_handler())
+        .branch(dptree::endpoint(handle_callback_query));
 
     let handler = dptree::entry()
-        .enter_dialogue::<Update, InMemStorage<State>, State>()
+        .enter_dialogue::<Update, Db, State>()
+        .map_async(|db: Arc<Db>, upd: Update| async move {
+            let Some(user) = upd.from() else {
+                return Lang::default();
+            };
+            let language_code = user.language_code.as_deref();
+            db.user_lang(ChatId::from(user.id).0, language_code)
+                .await
+                .unwrap_or(Lang::detect(language_code))
+        })
+        .branch(banned_user_handler())
         .branch(message_handler)
+        .branch(edited_message_handler())
+        .branch(reaction_handler())
         .branch(callback_handler);
 
     let db = Arc::new(Db::new().await?);
 
-    bot.set_my_commands(Command::bot_commands()).await?;
+    // for users whose language has no commands and descriptions of its own
+    bot.set_my_commands(Lang::default().bot_commands(Command::bot_commands()))
+        .await?;
+    bot.set_my_description()
+        .description(Lang::default().bot_description())
+        .await?;
+    bot.set_my_short_description()
+        .short_description(Lang::default().bot_short_description())
+        .await?;
+    for lang in Lang::ALL {
+        bot.set_my_commands(lang.bot_commands(Command::bot_commands()))
+            .language_code(lang.code())
+            .await?;
+        bot.set_my_description()
+            .description(lang.bot_description())
+            .language_code(lang.code())
+            .await?;
+        bot.set_my_short_description()
+            .short_description(lang.bot_short_description())
+            .language_code(lang.code())
+            .await?;
+    }
+
+    admin::init_admins(&bot, &db).await?;
+    tokio::spawn(broadcast::run_worker(bot.clone(), db.clone()));
+    tokio::spawn(retention::run_worker(db.clone()));
 
     let me = bot.get_me().await?;
     let username = me.username();
     info!("starting bot @{username}");
 
     Dispatcher::builder(bot, handler)
-        .dependencies(dptree::deps![db, InMemStorage::<State>::new()])
+        .dependencies(dptree::deps![db, Albums::default()])
         .enable_ctrlc_handler()
         .build()
         .dispatch()
@@ -151,18 +307,15 @@ async fn _main() -> Result<()> {
 async fn forward_message(
     bot: &Bot,
     db: &Db,
-    msg: &Message,
+    from: ChatId,
+    message_id: MessageId,
     recipient: ChatId,
     reply_for: Option<MessageId>,
 ) -> Result<MessageId> {
     let mut req = bot
-        .copy_message(recipient, msg.chat.id, msg.id)
-        .disable_notification(false);
-    if db.answer_tip_enabled(recipient.0).await? {
-        let inline_keyboard =
-            InlineKeyboardMarkup::new([[InlineKeyboardButton::callback("Ответить", "reply")]]);
-        req = req.reply_markup(inline_keyboard);
-    }
+        .copy_message(recipient, from, message_id)
+        .disable_notification(false)
+        .reply_markup(message_keyboard(db, recipient).await?);
 
     if let Some(reply_for) = reply_for {
         req = req.reply_parameters(ReplyParameters::new(reply_for).allow_sending_without_reply());
@@ -171,6 +324,68 @@ async fn forward_message(
     Ok(req.await?)
 }
 
+/// Copies a single message or a whole album, returning pairs of original and copied ids.
+/// Albums can't carry buttons, so these come in a separate message right after the album.
+async fn forward_parts(
+    bot: &Bot,
+    db: &Db,
+    from: ChatId,
+    parts: &[MessageId],
+    recipient: ChatId,
+    reply_for: Option<MessageId>,
+) -> Result<Vec<(i32, i32)>> {
+    let &[first, ..] = parts else {
+        bail!("nothing to forward");
+    };
+    if parts.len() == 1 {
+        let copy = forward_message(bot, db, from, first, recipient, reply_for).await?;
+        return Ok(vec![(first.0, copy.0)]);
+    }
+
+    let copies = bot
+        .copy_messages(recipient, from, parts.iter().copied())
+        .await?;
+    let anchor = reply_for
+        .or(copies.first().copied())
+        .context("album wasn't copied")?;
+    let lang = db.user_lang(recipient.0, None).await?;
+    let buttons = bot
+        .send_message(recipient, lang.album_buttons())
+        .reply_parameters(ReplyParameters::new(anchor).allow_sending_without_reply())
+        .reply_markup(message_keyboard(db, recipient).await?)
+        .await?;
+
+    let mut message_ids = parts
+        .iter()
+        .zip(copies)
+        .map(|(part, copy)| (part.0, copy.0))
+        .collect::<Vec<_>>();
+    // goes last, so that lookups by the first part find its copy rather than the buttons
+    message_ids.push((first.0, buttons.id.0));
+    Ok(message_ids)
+}
+
+fn part_ids(parts: &[Message]) -> Vec<MessageId> {
+    parts.iter().map(|part| part.id).collect()
+}
+
+/// Buttons under every delivered message, in the recipient's language.
+async fn message_keyboard(db: &Db, recipient: ChatId) -> Result<InlineKeyboardMarkup> {
+    let lang = db.user_lang(recipient.0, None).await?;
+    let mut keyboard = Vec::new();
+    if db.answer_tip_enabled(recipient.0).await? {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            lang.reply_button(),
+            "reply",
+        )]);
+    }
+    keyboard.push(vec![
+        InlineKeyboardButton::callback(lang.block_button(), "block"),
+        InlineKeyboardButton::callback(lang.report_button(), "report"),
+    ]);
+    Ok(InlineKeyboardMarkup::new(keyboard))
+}
+
 async fn handle_command_start(
     bot: Bot,
     me: Me,
@@ -178,28 +393,34 @@ async fn handle_command_start(
     link: String,
     db: Arc<Db>,
     dialogue: MyDialogue,
+    lang: Lang,
 ) -> Result<()> {
     if link.is_empty() {
         let my_link_code = db.get_user_link(msg.chat.id.0, None).await?;
-        bot.send_message(
-            msg.chat.id,
-            format!(
-                "Добро пожаловать! \
-        Чтобы начать получать анонимные вопросы, опубликуйте свою личную ссылку в канале: {}. \
-        Возможна отправка любых сообщений: текстовых, фото, стикеров и прочих.",
-                my_link_code.tme_url(&me)
-            ),
-        )
-        .await?;
-    } else if let Some(recipient_id) = db.user_id_by_link(&link).await? {
-        db.get_user_link(msg.chat.id.0, Some(recipient_id)).await?;
-        let sent_msg = bot
-            .send_message(
+        bot.send_message(msg.chat.id, lang.welcome(&my_link_code.tme_url(&me)))
+            .await?;
+    } else if let Some(link) = db.find_link(&link).await? {
+        let recipient_id = link.owner_id;
+        let my_link_code = db.get_user_link(msg.chat.id.0, Some(recipient_id)).await?;
+        if recipient_id != msg.chat.id.0 {
+            db.count_link_open(link.id).await?;
+        }
+
+        let inbox = db.get_inbox(recipient_id).await?;
+        if !inbox.is_open() {
+            bot.send_message(
                 msg.chat.id,
-                "Отправьте ваше анонимное сообщение (что угодно - текст, фото, стикер, ...):",
+                lang.inbox_closed(inbox.note.as_deref(), &my_link_code.tme_url(&me)),
             )
+            .reply_markup(KeyboardRemove::new())
+            .await?;
+            return Ok(());
+        }
+
+        let sent_msg = bot
+            .send_message(msg.chat.id, lang.send_your_message())
             .reply_markup(InlineKeyboardMarkup::new([[
-                InlineKeyboardButton::callback("Отмена", "cancel"),
+                InlineKeyboardButton::callback(lang.cancel_button(), "cancel"),
             ]]))
             .await?;
 
@@ -207,73 +428,175 @@ async fn handle_command_start(
             .update(State::WaitNewMessage(WaitNewMessage {
                 recipient_id,
                 clear_markup_message_id: sent_msg.id.0,
+                link_id: Some(link.id),
             }))
             .await?;
     } else {
         let my_link_code = db.get_user_link(msg.chat.id.0, None).await?;
-        bot.send_message(
-            msg.chat.id,
-            format!("Ссылка недействительна! Попросите автора создать новую ссылку. \
-            А вот, кстати, ваша собственная ссылка для получения анонимных вопросов и сообщений: {}", my_link_code.tme_url(&me)),
-        )
-        .reply_markup(KeyboardRemove::new())
+        bot.send_message(msg.chat.id, lang.link_invalid(&my_link_code.tme_url(&me)))
+            .reply_markup(KeyboardRemove::new())
+            .await?;
+    }
+    Ok(())
+}
+
+fn language_keyboard() -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(Lang::ALL.map(|l| {
+        [InlineKeyboardButton::callback(
+            l.name(),
+            format!("language:{}", l.code()),
+        )]
+    }))
+}
+
+async fn handle_command_language(bot: Bot, msg: Message, lang: Lang) -> Result<()> {
+    bot.send_message(msg.chat.id, lang.choose_language())
+        .reply_markup(language_keyboard())
         .await?;
+    Ok(())
+}
+
+async fn blocked_senders_message(
+    db: &Db,
+    user_id: i64,
+    lang: Lang,
+) -> Result<(String, InlineKeyboardMarkup)> {
+    let blocks = db.get_blocks(user_id).await?;
+    if blocks.is_empty() {
+        return Ok((
+            lang.no_blocked_senders().to_owned(),
+            InlineKeyboardMarkup::default(),
+        ));
     }
+
+    let buttons = blocks.iter().enumerate().map(|(i, block)| {
+        [InlineKeyboardButton::callback(
+            lang.unblock_button(i + 1, &block.timestamp.format("%d.%m.%Y").to_string()),
+            format!("unblock:{}", block.id),
+        )]
+    });
+    Ok((
+        lang.blocked_senders(blocks.len()),
+        InlineKeyboardMarkup::new(buttons),
+    ))
+}
+
+async fn handle_command_blocked(bot: Bot, db: Arc<Db>, msg: Message, lang: Lang) -> Result<()> {
+    let (text, keyboard) = blocked_senders_message(&db, msg.chat.id.0, lang).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .await?;
     Ok(())
 }
 
-async fn handle_state_start(
-    db: Arc<Db>,
-    bot: Bot,
-    msg: Message,
-    user_link: UserLink,
-    me: Me,
+async fn handle_state_start(ctx: MessageContext, msg: Message) -> Result<()> {
+    handle_start_parts(&ctx, &[msg]).await
+}
+
+/// Handles an album once all of its parts have arrived, the same way as a single message.
+async fn handle_album(
+    ctx: MessageContext,
+    parts: Vec<Message>,
+    dialogue: MyDialogue,
 ) -> Result<()> {
-    if msg.chat.id == ChatId(1004106925) {
-        if let Some(text) = msg.text() {
-            if let Some(broadcast_msg) = text.strip_prefix("/broadcast ") {
-                for user in db.get_all_users().await? {
-                    if let Err(e) = bot.send_message(ChatId(user), broadcast_msg).await {
-                        bot.send_message(msg.chat.id, e.to_string()).await?;
-                    };
-                }
-                bot.send_message(msg.chat.id, "Done!").await?;
-                return Ok(());
-            }
+    match dialogue.get_or_default().await? {
+        State::Start => handle_start_parts(&ctx, &parts).await,
+        State::WaitNewMessage(wait_state) => {
+            handle_wait_parts(&ctx, &parts, &dialogue, wait_state).await
         }
     }
+}
 
+/// `parts` is a single message or all parts of an album.
+async fn handle_start_parts(ctx: &MessageContext, parts: &[Message]) -> Result<()> {
+    let MessageContext {
+        bot,
+        db,
+        link_url,
+        lang,
+    } = ctx;
+    let lang = *lang;
+    let msg = parts.first().context("no message")?;
     if let Some(msg_reply_to) = msg.reply_to_message() {
-        process_reply(&db, &bot, msg_reply_to, &msg).await?;
+        process_reply(db, bot, msg_reply_to, parts, lang).await?;
     } else {
-        bot.send_message(msg.chat.id, format!("Кажется, вы отправили сообщение, но мы его не ждали... Может быть, \
-        вы хотели отправить кому-то сообщение или ответить на полученное? В таком случае перейдите по ссылке друга или свайпните \
-        полученное/отправленное сообщение влево (ответьте). А если вы хотите начать получать сообщения сами, то держите ссылку: {}", user_link.tme_url(&me)))
+        bot.send_message(msg.chat.id, lang.unexpected_message(link_url))
             .reply_markup(KeyboardRemove::new())
             .await?;
     }
     Ok(())
 }
 
-async fn process_reply(db: &Db, bot: &Bot, msg_reply_to: &Message, msg: &Message) -> Result<()> {
+async fn process_reply(
+    db: &Db,
+    bot: &Bot,
+    msg_reply_to: &Message,
+    parts: &[Message],
+    lang: Lang,
+) -> Result<()> {
+    let msg = parts.first().context("no message")?;
     ensure!(msg_reply_to.chat.id == msg.chat.id);
 
     if let Some(reply_for) = db
         .find_another_message(msg.chat.id.0, msg_reply_to.id.0)
         .await?
     {
-        match forward_message(
+        if db.is_blocked(reply_for.0, msg.chat.id.0).await? {
+            bot.send_message(msg.chat.id, lang.reply_not_delivered())
+                .reply_markup(KeyboardRemove::new())
+                .await?;
+            return Ok(());
+        }
+        if let Some(throttled) = rate_limit::check(db, msg.chat.id.0, reply_for.0).await? {
+            bot.send_message(msg.chat.id, lang.throttled(throttled.scope, throttled.wait))
+                .reply_markup(KeyboardRemove::new())
+                .await?;
+            return Ok(());
+        }
+
+        let filter = filters::check(db, reply_for.0, parts).await?;
+        if let Some(filter) = &filter
+            && filter.action != FilterAction::Warn
+        {
+            if filter.action == FilterAction::Hold {
+                filters::hold(bot, db, parts, reply_for.0, Some(reply_for.1), None, filter).await?;
+            } else {
+                filters::discard(db, parts, reply_for.0).await?;
+            }
+            // the sender can't tell filtered replies from delivered ones
+            bot.set_message_reaction(msg.chat.id, msg.id)
+                .reaction([ReactionType::Emoji {
+                    emoji: "👌".into()
+                }])
+                .await?;
+            return Ok(());
+        }
+
+        match forward_parts(
             bot,
             db,
-            msg,
+            msg.chat.id,
+            &part_ids(parts),
             ChatId(reply_for.0),
             Some(MessageId(reply_for.1)),
         )
         .await
         {
-            Ok(sent_msg_id) => {
-                db.save_message(msg.chat.id.0, msg.id.0, reply_for.0, sent_msg_id.0)
-                    .await?;
+            Ok(message_ids) => {
+                let replied = db.find_message(msg.chat.id.0, msg_reply_to.id.0).await?;
+                db.save_messages(
+                    msg.chat.id.0,
+                    reply_for.0,
+                    &message_ids,
+                    None,
+                    msg.media_group_id(),
+                    replied.as_ref(),
+                )
+                .await?;
+                if let Some(filter) = &filter {
+                    let sent_msg_id = MessageId(message_ids[0].1);
+                    filters::warn_recipient(bot, db, reply_for.0, sent_msg_id, filter).await?;
+                }
                 bot.set_message_reaction(msg.chat.id, msg.id)
                     .reaction([ReactionType::Emoji {
                         emoji: "👌".into()
@@ -281,78 +604,157 @@ async fn process_reply(db: &Db, bot: &Bot, msg_reply_to: &Message, msg: &Message
                     .await?;
             }
             Err(e) => {
-                bot.send_message(
-                        msg.chat.id,
-                        format!(
-                            "Не удалось ответить на сообщение: {e}. Возможно, получатель заблокировал бота.",
-                        ),
-                    )
+                bot.send_message(msg.chat.id, lang.reply_failed(&e.to_string()))
                     .reply_markup(KeyboardRemove::new())
                     .await?;
             }
         };
     } else {
-        bot.send_message(
-            msg.chat.id,
-            "Отвечать (свайпать слево) можно только на полученные и отправленные сообщения!",
-        )
-        .reply_markup(KeyboardRemove::new())
-        .await?;
+        // also the case for purged conversations and ones deleted along with an account
+        bot.send_message(msg.chat.id, lang.reply_not_found())
+            .reply_markup(KeyboardRemove::new())
+            .await?;
     };
 
     Ok(())
 }
 
 async fn handle_state_wait(
-    db: Arc<Db>,
-    bot: Bot,
+    ctx: MessageContext,
     msg: Message,
-    user_link: UserLink,
-    me: Me,
     dialogue: MyDialogue,
     wait_state: WaitNewMessage,
 ) -> Result<()> {
+    handle_wait_parts(&ctx, &[msg], &dialogue, wait_state).await
+}
+
+/// `parts` is a single message or all parts of an album.
+async fn handle_wait_parts(
+    ctx: &MessageContext,
+    parts: &[Message],
+    dialogue: &MyDialogue,
+    wait_state: WaitNewMessage,
+) -> Result<()> {
+    let MessageContext {
+        bot,
+        db,
+        link_url,
+        lang,
+    } = ctx;
+    let lang = *lang;
+    let msg = parts.first().context("no message")?;
+    let inbox = db.get_inbox(wait_state.recipient_id).await?;
     if msg.reply_to_message().is_some() {
+        bot.send_message(msg.chat.id, lang.replied_while_waiting())
+            .reply_markup(InlineKeyboardMarkup::new([[
+                InlineKeyboardButton::callback(lang.cancel_button(), "cancel"),
+            ]]))
+            .await?;
+    } else if db.get_user(wait_state.recipient_id).await?.is_none() {
+        // the recipient deleted their account after the sender opened the link
+        bot.send_message(msg.chat.id, lang.message_not_delivered(link_url))
+            .reply_markup(KeyboardRemove::new())
+            .await?;
+        bot.edit_message_reply_markup(msg.chat.id, MessageId(wait_state.clear_markup_message_id))
+            .await?;
+        dialogue.reset().await?;
+    } else if !inbox.is_open() {
         bot.send_message(
             msg.chat.id,
-            "Вы ответили на сообщение, пока мы ждали отправку вопроса.",
+            lang.inbox_closed(inbox.note.as_deref(), link_url),
         )
-        .reply_markup(InlineKeyboardMarkup::new([[
-            InlineKeyboardButton::callback("Отмена", "cancel"),
-        ]]))
+        .reply_markup(KeyboardRemove::new())
         .await?;
+        bot.edit_message_reply_markup(msg.chat.id, MessageId(wait_state.clear_markup_message_id))
+            .await?;
+        dialogue.reset().await?;
+    } else if db
+        .is_blocked(wait_state.recipient_id, msg.chat.id.0)
+        .await?
+    {
+        bot.send_message(msg.chat.id, lang.message_not_delivered(link_url))
+            .reply_markup(KeyboardRemove::new())
+            .await?;
+        bot.edit_message_reply_markup(msg.chat.id, MessageId(wait_state.clear_markup_message_id))
+            .await?;
+        dialogue.reset().await?;
+    } else if let Some(rejection) = parts.iter().find_map(|part| inbox.rejects(part)) {
+        let text = match rejection {
+            Rejection::Type(accepted) => lang.type_not_accepted(&lang.message_types(&accepted)),
+            Rejection::TooLong(max) => lang.text_too_long(max),
+        };
+        bot.send_message(msg.chat.id, text)
+            .reply_markup(InlineKeyboardMarkup::new([[
+                InlineKeyboardButton::callback(lang.cancel_button(), "cancel"),
+            ]]))
+            .await?;
+    } else if let Some(throttled) =
+        rate_limit::check(db, msg.chat.id.0, wait_state.recipient_id).await?
+    {
+        // the sender stays in the waiting state and may simply retry later
+        bot.send_message(msg.chat.id, lang.throttled(throttled.scope, throttled.wait))
+            .reply_markup(InlineKeyboardMarkup::new([[
+                InlineKeyboardButton::callback(lang.cancel_button(), "cancel"),
+            ]]))
+            .await?;
     } else {
-        match forward_message(&bot, &db, &msg, ChatId(wait_state.recipient_id), None).await {
-            Ok(sent_msg_id) => {
-                db.save_message(
-                    msg.chat.id.0,
-                    msg.id.0,
-                    wait_state.recipient_id,
-                    sent_msg_id.0,
-                )
-                .await?;
-                bot.send_message(
-                    msg.chat.id,
-                    format!(
-                        "Ваше сообщение отправлено! А вот, кстати, ваша \
-                        собственная ссылка для получения анонимных вопросов и сообщений: {}",
-                        user_link.tme_url(&me)
-                    ),
-                )
-                .reply_markup(KeyboardRemove::new())
-                .await?;
+        let filter = filters::check(db, wait_state.recipient_id, parts).await?;
+        let sent = match &filter {
+            // the sender can't tell filtered messages from delivered ones
+            Some(filter) if filter.action != FilterAction::Warn => {
+                if filter.action == FilterAction::Hold {
+                    filters::hold(
+                        bot,
+                        db,
+                        parts,
+                        wait_state.recipient_id,
+                        None,
+                        wait_state.link_id,
+                        filter,
+                    )
+                    .await?;
+                } else {
+                    filters::discard(db, parts, wait_state.recipient_id).await?;
+                }
+                Ok(Vec::new())
+            }
+            _ => {
+                let recipient = ChatId(wait_state.recipient_id);
+                forward_parts(bot, db, msg.chat.id, &part_ids(parts), recipient, None).await
+            }
+        };
+        match sent {
+            Ok(message_ids) => {
+                // held and dropped messages start no conversation
+                if let Some(&(_, sent_msg_id)) = message_ids.first() {
+                    db.save_messages(
+                        msg.chat.id.0,
+                        wait_state.recipient_id,
+                        &message_ids,
+                        wait_state.link_id,
+                        msg.media_group_id(),
+                        None,
+                    )
+                    .await?;
+                    if let Some(filter) = &filter {
+                        filters::warn_recipient(
+                            bot,
+                            db,
+                            wait_state.recipient_id,
+                            MessageId(sent_msg_id),
+                            filter,
+                        )
+                        .await?;
+                    }
+                }
+                bot.send_message(msg.chat.id, lang.message_sent(link_url))
+                    .reply_markup(unsend::unsend_keyboard(msg.id, lang))
+                    .await?;
             }
             Err(e) => {
-                bot.send_message(
-                    msg.chat.id,
-                    format!(
-                        "Не удалось отправить сообщение: {e}. Возможно, получатель заблокировал бота. \
-                        А вот, кстати, ваша собственная ссылка для получения анонимных вопросов и сообщений: {}",
-                        user_link.tme_url(&me)
-                    ),
-                )
-                .reply_markup(KeyboardRemove::new())
-                .await?;
+                bot.send_message(msg.chat.id, lang.send_failed(&e.to_string(), link_url))
+                    .reply_markup(KeyboardRemove::new())
+                    .await?;
             }
         }
         bot.edit_message_reply_markup(msg.chat.id, MessageId(wait_state.clear_markup_message_id))
@@ -368,6 +770,7 @@ async fn handle_callback_query(
     bot: Bot,
     q: CallbackQuery,
     dialogue: MyDialogue,
+    lang: Lang,
 ) -> Result<()> {
     if let Some(data) = &q.data
         && let Some(chat_id) = q.chat_id()
@@ -381,11 +784,7 @@ async fn handle_callback_query(
                         let link_code = db.get_user_link(chat_id.0, None).await?;
                         bot.send_message(
                             chat_id,
-                            format!(
-                                "Отправка сообщения отменена! А вот, кстати, ваша \
-                        собственная ссылка для получения анонимных вопросов и сообщений: {}",
-                                link_code.tme_url(&bot.get_me().await?)
-                            ),
+                            lang.send_cancelled(&link_code.tme_url(&bot.get_me().await?)),
                         )
                         .reply_markup(KeyboardRemove::new())
                         .await?;
@@ -400,15 +799,136 @@ async fn handle_callback_query(
                 bot.answer_callback_query(q.id)
                     .cache_time(3600)
                     .show_alert(true)
-                    .text("Для ответа используйте встроенную в Telegram функцию ответа на сообщение (свайпните влево)")
+                    .text(lang.reply_tip())
                     .await?;
-                bot.send_message(chat_id, "Для ответа используйте встроенную в Telegram функцию ответа на сообщение (свайпните влево).\n\n\
-                    Эта подсказка больше не будет отображаться.")
+                bot.send_message(chat_id, lang.reply_tip_disabled())
                     .reply_markup(KeyboardRemove::new())
                     .await?;
-                db.disable_answer_tip(chat_id.0).await?;
+                db.set_answer_tip(chat_id.0, false).await?;
+            }
+            "block" => {
+                let message_id = q.message.context("no message")?.id();
+                if let Some((sender_id, _)) =
+                    db.find_another_message(chat_id.0, message_id.0).await?
+                {
+                    db.block_sender(chat_id.0, sender_id).await?;
+                    bot.answer_callback_query(q.id)
+                        .show_alert(true)
+                        .text(lang.sender_blocked())
+                        .await?;
+                } else {
+                    bot.answer_callback_query(q.id).await?;
+                }
+            }
+            "report" => {
+                let message_id = q.message.context("no message")?.id();
+                moderation::ask_reason(&bot, chat_id, message_id, lang).await?;
+                bot.answer_callback_query(q.id).await?;
+            }
+            "settings_language" => {
+                bot.edit_message_text(
+                    chat_id,
+                    q.message.context("no message")?.id(),
+                    lang.choose_language(),
+                )
+                .reply_markup(language_keyboard())
+                .await?;
+                bot.answer_callback_query(q.id).await?;
+            }
+            "report_cancel" => {
+                bot.delete_message(chat_id, q.message.context("no message")?.id())
+                    .await?;
+                bot.answer_callback_query(q.id).await?;
+            }
+            _ => {
+                if let Some(new_lang) = data.strip_prefix("language:").and_then(Lang::from_code) {
+                    db.set_language(chat_id.0, new_lang).await?;
+                    if db.admin_role(chat_id.0).await?.is_some() {
+                        admin::set_admin_commands(&bot, &db, chat_id.0).await?;
+                    }
+                    bot.edit_message_text(
+                        chat_id,
+                        q.message.context("no message")?.id(),
+                        new_lang.language_changed(),
+                    )
+                    .await?;
+                    bot.answer_callback_query(q.id).await?;
+                } else if let Some(Ok(block_id)) = data.strip_prefix("unblock:").map(str::parse) {
+                    db.unblock(chat_id.0, block_id).await?;
+                    let (text, keyboard) = blocked_senders_message(&db, chat_id.0, lang).await?;
+                    bot.edit_message_text(chat_id, q.message.context("no message")?.id(), text)
+                        .reply_markup(keyboard)
+                        .await?;
+                    bot.answer_callback_query(q.id).await?;
+                } else if let Some(Ok(link_id)) = data.strip_prefix("disable_link:").map(str::parse)
+                {
+                    db.disable_link(chat_id.0, link_id).await?;
+                    let (text, keyboard) =
+                        links::links_message(&db, &bot.get_me().await?, chat_id.0, lang).await?;
+                    bot.edit_message_text(chat_id, q.message.context("no message")?.id(), text)
+                        .reply_markup(keyboard)
+                        .await?;
+                    bot.answer_callback_query(q.id).await?;
+                } else if let Some(Ok(filter_id)) =
+                    data.strip_prefix("remove_filter:").map(str::parse)
+                {
+                    db.remove_filter(chat_id.0, filter_id).await?;
+                    let (text, keyboard) = filters::filters_message(&db, chat_id.0, lang).await?;
+                    bot.edit_message_text(chat_id, q.message.context("no message")?.id(), text)
+                        .reply_markup(keyboard)
+                        .await?;
+                    bot.answer_callback_query(q.id).await?;
+                } else if let Some((action, Ok(held_id))) = data
+                    .split_once(':')
+                    .filter(|(action, _)| ["held_deliver", "held_delete"].contains(action))
+                    .map(|(action, id)| (action, id.parse()))
+                {
+                    let deliver = action == "held_deliver";
+                    filters::handle_held_action(&bot, &db, &q, chat_id, held_id, deliver, lang)
+                        .await?;
+                    bot.answer_callback_query(q.id).await?;
+                } else if let Some(Ok(message_id)) = data.strip_prefix("unsend:").map(str::parse) {
+                    let text = unsend::handle_unsend_button(
+                        &bot,
+                        &db,
+                        &q,
+                        chat_id,
+                        MessageId(message_id),
+                        lang,
+                    )
+                    .await?;
+                    bot.answer_callback_query(q.id)
+                        .show_alert(true)
+                        .text(text)
+                        .await?;
+                } else if let Some(Ok(page)) = data.strip_prefix("inbox_page:").map(str::parse) {
+                    received::handle_page(&bot, &db, &q, chat_id, page, lang).await?;
+                    bot.answer_callback_query(q.id).await?;
+                } else if let Some(Ok(message_id)) =
+                    data.strip_prefix("inbox_open:").map(str::parse)
+                {
+                    match received::handle_open(&bot, &db, chat_id, message_id, lang).await? {
+                        Some(text) => bot.answer_callback_query(q.id).text(text).await?,
+                        None => bot.answer_callback_query(q.id).await?,
+                    };
+                } else if let Some(action @ ("confirm" | "cancel")) =
+                    data.strip_prefix("delete_me:")
+                {
+                    let confirmed = action == "confirm";
+                    account::handle_delete_button(&bot, &db, &q, chat_id, confirmed, lang).await?;
+                    bot.answer_callback_query(q.id).await?;
+                } else if let Some(Ok(setting)) =
+                    data.strip_prefix("settings:").map(Setting::from_str)
+                {
+                    settings::handle_toggle(&bot, &db, &q, chat_id, setting, lang).await?;
+                    bot.answer_callback_query(q.id).await?;
+                } else if let Some(Ok(reason)) =
+                    data.strip_prefix("report:").map(ReportReason::from_str)
+                {
+                    moderation::submit_report(&bot, &db, &q, chat_id, reason, lang).await?;
+                    bot.answer_callback_query(q.id).await?;
+                }
             }
-            _ => {}
         }
     }
     Ok(())
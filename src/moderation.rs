@@ -0,0 +1,329 @@
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use teloxide::{
+    dispatching::UpdateHandler,
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId, ReplyParameters},
+};
+use tracing::*;
+
+use crate::{db::Db, i18n::Lang, Bot};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReportReason {
+    Spam,
+    Abuse,
+    Other,
+}
+
+impl ReportReason {
+    pub const ALL: [ReportReason; 3] =
+        [ReportReason::Spam, ReportReason::Abuse, ReportReason::Other];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportReason::Spam => "spam",
+            ReportReason::Abuse => "abuse",
+            ReportReason::Other => "other",
+        }
+    }
+}
+
+impl FromStr for ReportReason {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "spam" => Ok(ReportReason::Spam),
+            "abuse" => Ok(ReportReason::Abuse),
+            "other" => Ok(ReportReason::Other),
+            _ => bail!("unknown report reason: {s}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReportStatus {
+    Open,
+    Dismissed,
+    Warned,
+    Banned,
+}
+
+impl ReportStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Dismissed => "dismissed",
+            ReportStatus::Warned => "warned",
+            ReportStatus::Banned => "banned",
+        }
+    }
+}
+
+impl FromStr for ReportStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "open" => Ok(ReportStatus::Open),
+            "dismissed" => Ok(ReportStatus::Dismissed),
+            "warned" => Ok(ReportStatus::Warned),
+            "banned" => Ok(ReportStatus::Banned),
+            _ => bail!("unknown report status: {s}"),
+        }
+    }
+}
+
+/// A moderator's decision on a report, sent as `moderate:{report id}:{status}`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct ModerationAction {
+    report_id: i32,
+    decision: ReportStatus,
+}
+
+impl FromStr for ModerationAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some(("moderate", action)) = s.split_once(':') else {
+            bail!("unknown moderation action: {s}");
+        };
+        let (report_id, decision) = action
+            .split_once(':')
+            .context("moderation action without decision")?;
+        let decision = decision.parse()?;
+        if decision == ReportStatus::Open {
+            bail!("report can't be reopened");
+        }
+        Ok(ModerationAction {
+            report_id: report_id.parse()?,
+            decision,
+        })
+    }
+}
+
+pub fn moderation_callback_handler() -> UpdateHandler<anyhow::Error> {
+    dptree::filter_map(|q: CallbackQuery| q.data?.parse::<ModerationAction>().ok())
+        .filter_map_async(|db: Arc<Db>, q: CallbackQuery| async move {
+            db.admin_role(ChatId::from(q.from.id).0)
+                .await
+                .ok()
+                .flatten()
+        })
+        .endpoint(handle_moderation_action)
+}
+
+/// Chat where reports are sent for review, set with `MODERATION_CHAT_ID`.
+fn moderation_chat() -> Option<ChatId> {
+    let id = std::env::var("MODERATION_CHAT_ID").ok()?;
+    match id.trim().parse() {
+        Ok(id) => Some(ChatId(id)),
+        Err(e) => {
+            warn!("invalid MODERATION_CHAT_ID: {e}");
+            None
+        }
+    }
+}
+
+/// Asks for a report reason in reply to the reported message, so the answer can find it.
+pub async fn ask_reason(
+    bot: &Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    lang: Lang,
+) -> Result<()> {
+    let mut buttons = ReportReason::ALL
+        .map(|reason| {
+            vec![InlineKeyboardButton::callback(
+                lang.report_reason(reason),
+                format!("report:{}", reason.as_str()),
+            )]
+        })
+        .to_vec();
+    buttons.push(vec![InlineKeyboardButton::callback(
+        lang.cancel_button(),
+        "report_cancel",
+    )]);
+
+    bot.send_message(chat_id, lang.choose_report_reason())
+        .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply())
+        .reply_markup(InlineKeyboardMarkup::new(buttons))
+        .await?;
+    Ok(())
+}
+
+pub async fn submit_report(
+    bot: &Bot,
+    db: &Db,
+    q: &CallbackQuery,
+    chat_id: ChatId,
+    reason: ReportReason,
+    lang: Lang,
+) -> Result<()> {
+    let prompt = q
+        .message
+        .as_ref()
+        .and_then(|m| m.regular_message())
+        .context("no message")?;
+    let Some(reported) = prompt.reply_to_message() else {
+        bot.edit_message_text(chat_id, prompt.id, lang.report_failed())
+            .await?;
+        return Ok(());
+    };
+    let Some((sender_id, sender_message_id)) =
+        db.find_another_message(chat_id.0, reported.id.0).await?
+    else {
+        bot.edit_message_text(chat_id, prompt.id, lang.report_failed())
+            .await?;
+        return Ok(());
+    };
+
+    let Some(report_id) = db
+        .create_report(
+            chat_id.0,
+            reported.id.0,
+            sender_id,
+            sender_message_id,
+            reason,
+        )
+        .await?
+    else {
+        bot.edit_message_text(chat_id, prompt.id, lang.already_reported())
+            .await?;
+        return Ok(());
+    };
+
+    match moderation_chat() {
+        Some(moderation_chat) => {
+            let info = format!(
+                "Report #{report_id}: {}\nReporter: {} (message {})\nSender: {sender_id} (message {sender_message_id})",
+                reason.as_str(),
+                chat_id.0,
+                reported.id.0,
+            );
+            if let Err(e) =
+                send_to_moderators(bot, moderation_chat, chat_id, reported.id, info, report_id)
+                    .await
+            {
+                warn!("can't send report #{report_id} to moderators: {e}");
+            }
+        }
+        None => warn!("MODERATION_CHAT_ID is not set, report #{report_id} is only stored"),
+    }
+
+    bot.edit_message_text(chat_id, prompt.id, lang.report_sent())
+        .await?;
+    Ok(())
+}
+
+async fn send_to_moderators(
+    bot: &Bot,
+    moderation_chat: ChatId,
+    chat_id: ChatId,
+    message_id: MessageId,
+    info: String,
+    report_id: i32,
+) -> Result<()> {
+    let copy = bot
+        .copy_message(moderation_chat, chat_id, message_id)
+        .await?;
+    let buttons = [
+        ("Dismiss", ReportStatus::Dismissed),
+        ("Warn sender", ReportStatus::Warned),
+        ("Ban sender", ReportStatus::Banned),
+    ]
+    .map(|(text, decision)| {
+        InlineKeyboardButton::callback(text, format!("moderate:{report_id}:{}", decision.as_str()))
+    });
+    bot.send_message(moderation_chat, info)
+        .reply_parameters(ReplyParameters::new(copy).allow_sending_without_reply())
+        .reply_markup(InlineKeyboardMarkup::new([buttons]))
+        .await?;
+    Ok(())
+}
+
+async fn handle_moderation_action(
+    bot: Bot,
+    db: Arc<Db>,
+    q: CallbackQuery,
+    action: ModerationAction,
+) -> Result<()> {
+    // the reporter could have deleted their account since
+    let Some(report) = db.get_report(action.report_id).await? else {
+        bot.answer_callback_query(q.id)
+            .text(format!("Report #{} no longer exists", action.report_id))
+            .await?;
+        return Ok(());
+    };
+    if action.decision == ReportStatus::Banned && db.admin_role(report.sender_id).await?.is_some() {
+        bot.answer_callback_query(q.id)
+            .text("Admins can't be banned")
+            .await?;
+        return Ok(());
+    }
+    let moderator_id = ChatId::from(q.from.id).0;
+    if !db
+        .decide_report(report.id, action.decision, moderator_id)
+        .await?
+    {
+        bot.answer_callback_query(q.id)
+            .text(format!(
+                "Report #{} is already {}",
+                report.id, report.status
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    if action.decision == ReportStatus::Banned {
+        db.ban_user(
+            report.sender_id,
+            Some(format!("report #{}: {}", report.id, report.reason)),
+            None,
+            moderator_id,
+        )
+        .await?;
+    }
+    if action.decision == ReportStatus::Warned {
+        let sender_lang = db.user_lang(report.sender_id, None).await?;
+        if let Err(e) = bot
+            .send_message(ChatId(report.sender_id), sender_lang.sender_warned())
+            .reply_parameters(
+                ReplyParameters::new(MessageId(report.sender_message_id))
+                    .allow_sending_without_reply(),
+            )
+            .await
+        {
+            warn!("can't warn {}: {e}", report.sender_id);
+        }
+    }
+
+    let reporter_lang = db.user_lang(report.reporter_id, None).await?;
+    if let Err(e) = bot
+        .send_message(
+            ChatId(report.reporter_id),
+            reporter_lang.report_reviewed(action.decision),
+        )
+        .reply_parameters(
+            ReplyParameters::new(MessageId(report.reporter_message_id))
+                .allow_sending_without_reply(),
+        )
+        .await
+    {
+        warn!("can't notify reporter {}: {e}", report.reporter_id);
+    }
+
+    if let Some(message) = q.message.as_ref().and_then(|m| m.regular_message()) {
+        let text = format!(
+            "{}\n\nDecision: {} by {moderator_id}",
+            message.text().unwrap_or_default(),
+            action.decision.as_str()
+        );
+        bot.edit_message_text(message.chat.id, message.id, text)
+            .await?;
+    }
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::{str::FromStr, sync::LazyLock};
+
+use anyhow::{bail, Context, Result};
+use sea_orm::{prelude::DateTime, FromQueryResult};
+use tracing::*;
+
+use crate::db::Db;
+
+/// Limits are read once from `RATE_LIMIT_SENDER`, `RATE_LIMIT_PAIR` and `RATE_LIMIT_RECIPIENT`,
+/// each formatted as `<messages>/<seconds>`.
+static LIMITS: LazyLock<[(LimitScope, Limit); 3]> = LazyLock::new(|| {
+    [
+        (
+            LimitScope::Sender,
+            Limit::from_env("RATE_LIMIT_SENDER", 20, 60),
+        ),
+        (LimitScope::Pair, Limit::from_env("RATE_LIMIT_PAIR", 5, 60)),
+        (
+            LimitScope::Recipient,
+            Limit::from_env("RATE_LIMIT_RECIPIENT", 60, 60),
+        ),
+    ]
+});
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LimitScope {
+    /// Everything the sender sends, to anyone.
+    Sender,
+    /// What the sender sends to one recipient.
+    Pair,
+    /// Everything the recipient receives, from anyone.
+    Recipient,
+}
+
+impl LimitScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LimitScope::Sender => "sender",
+            LimitScope::Pair => "pair",
+            LimitScope::Recipient => "recipient",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Limit {
+    pub messages: u64,
+    pub seconds: u64,
+}
+
+impl Limit {
+    fn from_env(var: &str, messages: u64, seconds: u64) -> Self {
+        match std::env::var(var).map(|v| v.parse()) {
+            Ok(Ok(limit)) => limit,
+            Ok(Err(e)) => {
+                warn!("invalid {var}, using {messages}/{seconds}: {e}");
+                Limit { messages, seconds }
+            }
+            Err(_) => Limit { messages, seconds },
+        }
+    }
+}
+
+impl FromStr for Limit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (messages, seconds) = s.split_once('/').context("expected <messages>/<seconds>")?;
+        let limit = Limit {
+            messages: messages.trim().parse()?,
+            seconds: seconds.trim().parse()?,
+        };
+        if limit.messages == 0 || limit.seconds == 0 {
+            bail!("limit can't be zero");
+        }
+        Ok(limit)
+    }
+}
+
+/// A sender's throttled attempts over some period, for admins.
+#[derive(Clone, Debug, FromQueryResult)]
+pub struct ThrottledSender {
+    pub sender_id: i64,
+    pub count: i64,
+    pub last: DateTime,
+    /// Comma-separated scopes the sender hit.
+    pub scopes: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Throttled {
+    pub scope: LimitScope,
+    /// Seconds until the sender may try again.
+    pub wait: u64,
+}
+
+/// Checks whether `sender` may send one more message to `recipient` right now,
+/// logging the attempt for admins if not.
+pub async fn check(db: &Db, sender: i64, recipient: i64) -> Result<Option<Throttled>> {
+    for &(scope, limit) in LIMITS.iter() {
+        let (from, to) = match scope {
+            LimitScope::Sender => (Some(sender), None),
+            LimitScope::Pair => (Some(sender), Some(recipient)),
+            LimitScope::Recipient => (None, Some(recipient)),
+        };
+        let (count, wait) = db.recent_messages(from, to, limit.seconds).await?;
+        if count >= limit.messages {
+            db.log_throttle(sender, recipient, scope).await?;
+            return Ok(Some(Throttled {
+                scope,
+                wait: wait.max(1),
+            }));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use teloxide::{
+    dispatching::UpdateHandler,
+    prelude::*,
+    types::{MessageId, MessageReactionUpdated, ReactionType},
+};
+use tracing::*;
+
+use crate::{db::Db, Bot};
+
+/// Mirrors emoji reactions on a delivered message or its copy to the other side.
+/// Teloxide hints `allowed_updates` from the handler tree, so this branch alone subscribes
+/// the bot to `message_reaction` updates.
+pub fn reaction_handler() -> UpdateHandler<anyhow::Error> {
+    Update::filter_message_reaction_updated().endpoint(handle_reaction)
+}
+
+async fn handle_reaction(bot: Bot, db: Arc<Db>, reaction: MessageReactionUpdated) -> Result<()> {
+    let chat_id = reaction.chat.id;
+    let Some((other_chat_id, other_message_id)) = db
+        .find_another_message(chat_id.0, reaction.message_id.0)
+        .await?
+    else {
+        return Ok(());
+    };
+    if db.is_blocked(other_chat_id, chat_id.0).await? {
+        return Ok(());
+    }
+
+    // bots may set a single reaction and only a regular emoji one
+    let emoji = reaction
+        .new_reaction
+        .into_iter()
+        .rfind(|r| matches!(r, ReactionType::Emoji { .. }));
+    if let Err(e) = bot
+        .set_message_reaction(ChatId(other_chat_id), MessageId(other_message_id))
+        .reaction(emoji.into_iter().collect::<Vec<_>>())
+        .await
+    {
+        warn!(
+            "can't mirror reaction on message {} in {chat_id}: {e}",
+            reaction.message_id
+        );
+    }
+    Ok(())
+}
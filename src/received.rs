@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use sea_orm::{prelude::DateTime, FromQueryResult};
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId, ReplyParameters},
+    ApiError, RequestError,
+};
+
+use crate::{db::Db, i18n::Lang, Bot};
+
+const PAGE_SIZE: u64 = 10;
+
+/// A message the user received, as listed by /inbox. An album is listed once, by its first part.
+#[derive(Clone, Debug, FromQueryResult)]
+pub struct ReceivedMessage {
+    pub id: i32,
+    pub timestamp: DateTime,
+    /// Label of the link it was sent via.
+    pub label: Option<String>,
+    /// Whether the user has written to the conversation since.
+    pub answered: bool,
+}
+
+/// The page of received messages, newest first. Every message has a button sent as
+/// `inbox_open:{message id}`, and the page buttons are sent as `inbox_page:{page}`.
+pub async fn inbox_message(
+    db: &Db,
+    user_id: i64,
+    page: u64,
+    lang: Lang,
+) -> Result<(String, InlineKeyboardMarkup)> {
+    let total = db.count_received(user_id).await?;
+    if total == 0 {
+        return Ok((
+            lang.no_received_messages().to_owned(),
+            InlineKeyboardMarkup::default(),
+        ));
+    }
+    let pages = total.div_ceil(PAGE_SIZE);
+    // the list may have shrunk since the page was shown
+    let page = page.min(pages - 1);
+    let messages = db
+        .received_messages(user_id, page * PAGE_SIZE, PAGE_SIZE)
+        .await?;
+
+    let mut buttons = messages
+        .iter()
+        .enumerate()
+        .map(|(i, message)| {
+            vec![InlineKeyboardButton::callback(
+                lang.received_button(
+                    (page * PAGE_SIZE) as usize + i + 1,
+                    &message.timestamp.format("%d.%m.%Y %H:%M").to_string(),
+                    message.label.as_deref(),
+                    message.answered,
+                ),
+                format!("inbox_open:{}", message.id),
+            )]
+        })
+        .collect::<Vec<_>>();
+    let mut navigation = Vec::new();
+    if page > 0 {
+        navigation.push(InlineKeyboardButton::callback(
+            "◀️",
+            format!("inbox_page:{}", page - 1),
+        ));
+    }
+    if page + 1 < pages {
+        navigation.push(InlineKeyboardButton::callback(
+            "▶️",
+            format!("inbox_page:{}", page + 1),
+        ));
+    }
+    if !navigation.is_empty() {
+        buttons.push(navigation);
+    }
+
+    Ok((
+        lang.received_messages(total, page + 1, pages),
+        InlineKeyboardMarkup::new(buttons),
+    ))
+}
+
+pub async fn handle_command_inbox(bot: Bot, db: Arc<Db>, msg: Message, lang: Lang) -> Result<()> {
+    let (text, keyboard) = inbox_message(&db, msg.chat.id.0, 0, lang).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+/// Redraws the list in place with another page.
+pub async fn handle_page(
+    bot: &Bot,
+    db: &Db,
+    q: &CallbackQuery,
+    chat_id: ChatId,
+    page: u64,
+    lang: Lang,
+) -> Result<()> {
+    let (text, keyboard) = inbox_message(db, chat_id.0, page, lang).await?;
+    bot.edit_message_text(
+        chat_id,
+        q.message.as_ref().context("no message")?.id(),
+        text,
+    )
+    .reply_markup(keyboard)
+    .await?;
+    Ok(())
+}
+
+/// Replies to the received message so that tapping the reply scrolls to it,
+/// returns the text for the callback answer if it's gone.
+pub async fn handle_open(
+    bot: &Bot,
+    db: &Db,
+    chat_id: ChatId,
+    message_id: i32,
+    lang: Lang,
+) -> Result<Option<&'static str>> {
+    let Some(message) = db.get_received_message(chat_id.0, message_id).await? else {
+        return Ok(Some(lang.received_message_gone()));
+    };
+    match bot
+        .send_message(chat_id, lang.received_message_here())
+        .reply_parameters(ReplyParameters::new(MessageId(
+            message.recipient_message_id,
+        )))
+        .await
+    {
+        Ok(_) => Ok(None),
+        // the user has deleted it
+        Err(RequestError::Api(ApiError::MessageToReplyNotFound)) => {
+            Ok(Some(lang.received_message_gone()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use anyhow::Result;
+use teloxide::prelude::*;
+use tracing::*;
+
+use crate::{db::Db, i18n::Lang, Bot};
+
+const DEFAULT_RETENTION_DAYS: u32 = 365;
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long delivered messages are kept, read once from `RETENTION_DAYS`.
+/// Users may choose a shorter period for messages they sent or received with /retention.
+pub static RETENTION_DAYS: LazyLock<u32> =
+    LazyLock::new(
+        || match std::env::var("RETENTION_DAYS").map(|v| v.parse()) {
+            Ok(Ok(days)) if days > 0 => days,
+            Ok(_) => {
+                warn!("invalid RETENTION_DAYS, using {DEFAULT_RETENTION_DAYS}");
+                DEFAULT_RETENTION_DAYS
+            }
+            Err(_) => DEFAULT_RETENTION_DAYS,
+        },
+    );
+
+/// Periodically deletes messages older than the retention period of either side,
+/// along with conversations left without messages.
+pub async fn run_worker(db: Arc<Db>) {
+    loop {
+        match db.purge_messages(*RETENTION_DAYS).await {
+            Ok((messages, conversations)) => {
+                info!("retention purge: {messages} messages, {conversations} conversations")
+            }
+            Err(e) => error!("retention purge failed: {e:?}"),
+        }
+        tokio::time::sleep(PURGE_INTERVAL).await;
+    }
+}
+
+pub async fn handle_command_retention(
+    bot: Bot,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+    args: String,
+) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let max = *RETENTION_DAYS;
+    let args = args.trim();
+    let current = db
+        .retention_days(msg.chat.id.0)
+        .await?
+        .map_or(max, |days| days.min(max));
+    let text = if args.is_empty() {
+        lang.retention_usage(current, max)
+    } else if args.eq_ignore_ascii_case("off") {
+        db.set_retention_days(msg.chat.id.0, None).await?;
+        lang.retention_reset(max)
+    } else {
+        match args.parse::<u32>() {
+            Ok(days) if (1..=max).contains(&days) => {
+                db.set_retention_days(msg.chat.id.0, Some(days)).await?;
+                lang.retention_set(days)
+            }
+            _ => lang.retention_usage(current, max),
+        }
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use entities::users;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+};
+
+use crate::{db::Db, i18n::Lang, retention::RETENTION_DAYS, Bot};
+
+/// A setting switched on and off right in the /settings menu, its button sends
+/// `settings:{setting}`. A new setting needs a variant here, its state in `is_on` and `set`,
+/// and a name in `Lang::setting_name`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Setting {
+    /// The "Reply" button under delivered messages.
+    AnswerTip,
+    /// Whether new messages are accepted, see /pause.
+    Inbox,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 2] = [Setting::AnswerTip, Setting::Inbox];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Setting::AnswerTip => "answer_tip",
+            Setting::Inbox => "inbox",
+        }
+    }
+
+    fn is_on(self, user: &users::Model) -> bool {
+        match self {
+            Setting::AnswerTip => user.answer_tip,
+            Setting::Inbox => !user.paused,
+        }
+    }
+
+    async fn set(self, db: &Db, user_id: i64, on: bool) -> Result<()> {
+        match self {
+            Setting::AnswerTip => db.set_answer_tip(user_id, on).await,
+            Setting::Inbox => db.set_paused(user_id, !on, None).await,
+        }
+    }
+}
+
+impl FromStr for Setting {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Setting::ALL
+            .into_iter()
+            .find(|setting| setting.as_str() == s)
+        {
+            Some(setting) => Ok(setting),
+            None => bail!("unknown setting: {s}"),
+        }
+    }
+}
+
+/// The menu lists toggles as buttons and points to commands for the settings that take text.
+pub async fn settings_message(
+    db: &Db,
+    user_id: i64,
+    lang: Lang,
+) -> Result<(String, InlineKeyboardMarkup)> {
+    let user = db.get_user(user_id).await?.context("user not found")?;
+    let inbox = db.get_inbox(user_id).await?;
+    let filters = db.get_filters(user_id).await?.len();
+    let retention_days = user
+        .retention_days
+        .map_or(*RETENTION_DAYS, |days| (days as u32).min(*RETENTION_DAYS));
+
+    let schedule = inbox.schedule.map(|s| s.describe());
+    let accepted_types = inbox.accepted_types.map(|t| lang.message_types(&t));
+    let text = lang.settings(
+        schedule.as_deref(),
+        accepted_types.as_deref(),
+        inbox.max_text_length,
+        filters,
+        retention_days,
+    );
+
+    let mut buttons = Setting::ALL
+        .map(|setting| {
+            vec![InlineKeyboardButton::callback(
+                lang.setting_button(setting, setting.is_on(&user)),
+                format!("settings:{}", setting.as_str()),
+            )]
+        })
+        .to_vec();
+    buttons.push(vec![InlineKeyboardButton::callback(
+        lang.language_button(),
+        "settings_language",
+    )]);
+    Ok((text, InlineKeyboardMarkup::new(buttons)))
+}
+
+pub async fn handle_command_settings(
+    bot: Bot,
+    db: Arc<Db>,
+    msg: Message,
+    lang: Lang,
+) -> Result<()> {
+    db.get_user_link(msg.chat.id.0, None).await?;
+    let (text, keyboard) = settings_message(&db, msg.chat.id.0, lang).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+/// Flips the setting and redraws the menu in place.
+pub async fn handle_toggle(
+    bot: &Bot,
+    db: &Db,
+    q: &CallbackQuery,
+    chat_id: ChatId,
+    setting: Setting,
+    lang: Lang,
+) -> Result<()> {
+    let user = db.get_user(chat_id.0).await?.context("user not found")?;
+    setting.set(db, chat_id.0, !setting.is_on(&user)).await?;
+
+    let (text, keyboard) = settings_message(db, chat_id.0, lang).await?;
+    bot.edit_message_text(
+        chat_id,
+        q.message.as_ref().context("no message")?.id(),
+        text,
+    )
+    .reply_markup(keyboard)
+    .await?;
+    Ok(())
+}
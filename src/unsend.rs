@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Duration;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId},
+    ApiError, RequestError,
+};
+
+use crate::{db::Db, i18n::Lang, Bot};
+
+/// Telegram doesn't let bots delete messages older than this.
+pub const UNSEND_WINDOW_HOURS: i64 = 48;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Unsend {
+    Retracted,
+    AlreadyRetracted,
+    TooLate,
+    NotFound,
+}
+
+/// Button for the confirmation of a delivered message, sent as `unsend:{sender message id}`.
+pub fn unsend_keyboard(message_id: MessageId, lang: Lang) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new([[InlineKeyboardButton::callback(
+        lang.unsend_button(),
+        format!("unsend:{}", message_id.0),
+    )]])
+}
+
+/// Deletes the recipient's copy of the sender's message or album, or forgets it if it's still held
+/// or was dropped.
+pub async fn unsend(bot: &Bot, db: &Db, sender: ChatId, message_id: MessageId) -> Result<Unsend> {
+    if db.remove_held(sender.0, message_id.0).await?
+        || db.remove_dropped(sender.0, message_id.0).await?
+    {
+        return Ok(Unsend::Retracted);
+    }
+    let Some(message) = db.find_sent_message(sender.0, message_id.0).await? else {
+        return Ok(Unsend::NotFound);
+    };
+    if message.retracted_at.is_some() {
+        return Ok(Unsend::AlreadyRetracted);
+    }
+    if chrono::Utc::now().naive_utc() - message.timestamp > Duration::hours(UNSEND_WINDOW_HOURS) {
+        return Ok(Unsend::TooLate);
+    }
+
+    // an album goes away as a whole, along with the buttons sent after it
+    let messages = match &message.media_group_id {
+        Some(media_group_id) => db.find_album(sender.0, media_group_id).await?,
+        None => vec![message],
+    };
+    let copies = messages
+        .iter()
+        .map(|m| MessageId(m.recipient_message_id))
+        .collect::<Vec<_>>();
+    let recipient = ChatId(messages[0].recipient_id);
+    let deleted = if let [copy] = copies[..] {
+        bot.delete_message(recipient, copy).await
+    } else {
+        bot.delete_messages(recipient, copies).await
+    };
+    match deleted {
+        // the recipient has already deleted it
+        Ok(_) | Err(RequestError::Api(ApiError::MessageToDeleteNotFound)) => {}
+        Err(RequestError::Api(ApiError::MessageCantBeDeleted)) => return Ok(Unsend::TooLate),
+        Err(e) => return Err(e.into()),
+    }
+    db.retract_messages(messages.iter().map(|m| m.id).collect())
+        .await?;
+    Ok(Unsend::Retracted)
+}
+
+fn outcome_text(outcome: Unsend, lang: Lang) -> String {
+    match outcome {
+        Unsend::Retracted => lang.message_unsent().to_owned(),
+        Unsend::AlreadyRetracted => lang.message_already_unsent().to_owned(),
+        Unsend::TooLate => lang.unsend_too_late(UNSEND_WINDOW_HOURS),
+        Unsend::NotFound => lang.unsend_not_found().to_owned(),
+    }
+}
+
+pub async fn handle_command_unsend(bot: Bot, db: Arc<Db>, msg: Message, lang: Lang) -> Result<()> {
+    let text = match msg.reply_to_message() {
+        Some(reply_to) => outcome_text(unsend(&bot, &db, msg.chat.id, reply_to.id).await?, lang),
+        None => lang.unsend_usage().to_owned(),
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+/// Handles the button under the confirmation, removing it once it's no use,
+/// and returns the text for the callback answer.
+pub async fn handle_unsend_button(
+    bot: &Bot,
+    db: &Db,
+    q: &CallbackQuery,
+    chat_id: ChatId,
+    message_id: MessageId,
+    lang: Lang,
+) -> Result<String> {
+    let outcome = unsend(bot, db, chat_id, message_id).await?;
+    if let Some(confirmation) = &q.message {
+        bot.edit_message_reply_markup(chat_id, confirmation.id())
+            .await?;
+    }
+    Ok(outcome_text(outcome, lang))
+}